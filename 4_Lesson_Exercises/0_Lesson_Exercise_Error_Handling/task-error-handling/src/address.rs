@@ -0,0 +1,68 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ModernTxError;
+use crate::amount::Asset;
+
+use self::btc::BtcAddress;
+use self::eth::EthAddress;
+use self::sol::SolAddress;
+
+pub mod base58;
+pub mod btc;
+pub mod eth;
+pub mod sol;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Testnet,
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Network::Mainnet => write!(f, "mainnet"),
+            Network::Testnet => write!(f, "testnet"),
+        }
+    }
+}
+
+/// An address validated by the rules of its asset's chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Address {
+    Btc(BtcAddress),
+    Eth(EthAddress),
+    Sol(SolAddress),
+}
+
+impl Address {
+    pub fn parse(asset: Asset, address: &str) -> Result<Self, ModernTxError> {
+        Ok(match asset {
+            Asset::Btc => Address::Btc(address.parse()?),
+            Asset::Eth => Address::Eth(address.parse()?),
+            Asset::Sol => Address::Sol(address.parse()?),
+        })
+    }
+
+    /// The network encoded in the address itself. Only BTC addresses carry one.
+    pub fn network(&self) -> Option<Network> {
+        match self {
+            Address::Btc(address) => Some(address.network()),
+            Address::Eth(_) | Address::Sol(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Address::Btc(address) => write!(f, "{}", address),
+            Address::Eth(address) => write!(f, "{}", address),
+            Address::Sol(address) => write!(f, "{}", address),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Returned when a character outside the base58 alphabet is found.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InvalidChar {
+    pub ch: char,
+    pub pos: usize,
+}
+
+/// Decodes a base58 string into bytes. Leading '1's become leading zero bytes.
+pub fn decode(input: &str) -> Result<Vec<u8>, InvalidChar> {
+    // little-endian base256 accumulator
+    let mut bytes: Vec<u8> = Vec::new();
+    for (pos, ch) in input.char_indices() {
+        let digit = ALPHABET
+            .iter()
+            .position(|&c| c as char == ch)
+            .ok_or(InvalidChar { ch, pos })?;
+
+        let mut carry = digit as u32;
+        for byte in bytes.iter_mut() {
+            carry += *byte as u32 * 58;
+            *byte = (carry & 0xff) as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push((carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+
+    let leading_zeros = input.bytes().take_while(|&b| b == b'1').count();
+    let mut decoded = vec![0u8; leading_zeros];
+    decoded.extend(bytes.iter().rev());
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_leading_ones_as_zero_bytes() {
+        assert_eq!(decode("1111"), Ok(vec![0, 0, 0, 0]));
+        assert_eq!(decode("2"), Ok(vec![1]));
+        assert_eq!(decode("5Q"), Ok(vec![0xff]));
+    }
+
+    #[test]
+    fn reports_invalid_character_position() {
+        assert_eq!(decode("12O4"), Err(InvalidChar { ch: 'O', pos: 2 }));
+    }
+}
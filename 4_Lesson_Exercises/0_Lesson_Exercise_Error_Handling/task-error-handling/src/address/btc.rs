@@ -0,0 +1,351 @@
+use std::fmt;
+use std::str::FromStr;
+
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use super::{Network, base58};
+
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32_CONST: u32 = 1;
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bech32Variant {
+    Bech32,
+    Bech32m,
+}
+
+impl fmt::Display for Bech32Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bech32Variant::Bech32 => write!(f, "bech32"),
+            Bech32Variant::Bech32m => write!(f, "bech32m"),
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum BtcAddressError {
+    #[error("address is empty")]
+    Empty,
+    #[error("invalid character '{ch}' at position {pos}")]
+    InvalidCharacter { ch: char, pos: usize },
+    #[error("invalid address length: {0} bytes")]
+    InvalidLength(usize),
+    #[error("invalid decoded length: {0} bytes, expected 25")]
+    InvalidPayloadLength(usize),
+    #[error("checksum mismatch")]
+    BadChecksum,
+    #[error("unknown version byte 0x{0:02x}")]
+    UnknownVersion(u8),
+    #[error("mixed-case bech32 address")]
+    MixedCase,
+    #[error("unknown human-readable part '{0}'")]
+    UnknownHrp(String),
+    #[error("invalid witness version {0}")]
+    InvalidWitnessVersion(u8),
+    #[error("invalid witness program length {len} for version {version}")]
+    InvalidProgramLength { version: u8, len: usize },
+    #[error("witness version {version} requires a {expected} checksum")]
+    WrongChecksumVariant {
+        version: u8,
+        expected: Bech32Variant,
+    },
+    #[error("invalid padding in witness program")]
+    InvalidPadding,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BtcAddressType {
+    P2pkh,
+    P2sh,
+    P2wpkh,
+    P2wsh,
+    P2tr,
+    /// A future SegWit version (v2..=v16), valid but not yet in use.
+    Witness(u8),
+}
+
+/// A Bitcoin address whose encoding, checksum and version rules have been verified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BtcAddress {
+    address: String,
+    network: Network,
+    address_type: BtcAddressType,
+}
+
+impl BtcAddress {
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    pub fn address_type(&self) -> BtcAddressType {
+        self.address_type
+    }
+}
+
+impl fmt::Display for BtcAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.address)
+    }
+}
+
+impl FromStr for BtcAddress {
+    type Err = BtcAddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(BtcAddressError::Empty);
+        }
+        let lower = s.to_ascii_lowercase();
+        if lower.starts_with("bc1") || lower.starts_with("tb1") {
+            decode_segwit(s)
+        } else {
+            decode_base58check(s)
+        }
+    }
+}
+
+fn sha256d(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(Sha256::digest(data)).into()
+}
+
+fn decode_base58check(s: &str) -> Result<BtcAddress, BtcAddressError> {
+    let bytes = base58::decode(s).map_err(|e| BtcAddressError::InvalidCharacter {
+        ch: e.ch,
+        pos: e.pos,
+    })?;
+    // version byte + 20 byte hash + 4 byte checksum
+    if bytes.len() != 25 {
+        return Err(BtcAddressError::InvalidPayloadLength(bytes.len()));
+    }
+    let (payload, checksum) = bytes.split_at(21);
+    if sha256d(payload)[..4] != *checksum {
+        return Err(BtcAddressError::BadChecksum);
+    }
+
+    let (network, address_type) = match payload[0] {
+        0x00 => (Network::Mainnet, BtcAddressType::P2pkh),
+        0x05 => (Network::Mainnet, BtcAddressType::P2sh),
+        0x6f => (Network::Testnet, BtcAddressType::P2pkh),
+        0xc4 => (Network::Testnet, BtcAddressType::P2sh),
+        version => return Err(BtcAddressError::UnknownVersion(version)),
+    };
+    Ok(BtcAddress {
+        address: s.to_string(),
+        network,
+        address_type,
+    })
+}
+
+fn bech32_polymod(values: impl Iterator<Item = u8>) -> u32 {
+    const GEN: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    let mut chk: u32 = 1;
+    for v in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x01ff_ffff) << 5) ^ v as u32;
+        for (i, g) in GEN.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+fn decode_segwit(s: &str) -> Result<BtcAddress, BtcAddressError> {
+    if !(14..=90).contains(&s.len()) {
+        return Err(BtcAddressError::InvalidLength(s.len()));
+    }
+    // The prefix check in from_str guarantees an ASCII "xx1" start.
+    let (hrp, data_part) = s.split_at(s.rfind('1').unwrap_or(2));
+    let data_part = &data_part[1..];
+
+    let mut data = Vec::with_capacity(data_part.len());
+    for (i, ch) in data_part.char_indices() {
+        let value = BECH32_CHARSET
+            .iter()
+            .position(|&c| c as char == ch.to_ascii_lowercase())
+            .ok_or(BtcAddressError::InvalidCharacter {
+                ch,
+                pos: hrp.len() + 1 + i,
+            })?;
+        data.push(value as u8);
+    }
+    // witness version + 6 checksum characters at minimum
+    if data.len() < 7 {
+        return Err(BtcAddressError::InvalidLength(s.len()));
+    }
+    let has_lower = s.chars().any(|c| c.is_ascii_lowercase());
+    let has_upper = s.chars().any(|c| c.is_ascii_uppercase());
+    if has_lower && has_upper {
+        return Err(BtcAddressError::MixedCase);
+    }
+
+    let hrp = hrp.to_ascii_lowercase();
+    let network = match hrp.as_str() {
+        "bc" => Network::Mainnet,
+        "tb" => Network::Testnet,
+        _ => return Err(BtcAddressError::UnknownHrp(hrp)),
+    };
+
+    let expanded = hrp
+        .bytes()
+        .map(|b| b >> 5)
+        .chain(std::iter::once(0))
+        .chain(hrp.bytes().map(|b| b & 0x1f))
+        .chain(data.iter().copied());
+    let variant = match bech32_polymod(expanded) {
+        BECH32_CONST => Bech32Variant::Bech32,
+        BECH32M_CONST => Bech32Variant::Bech32m,
+        _ => return Err(BtcAddressError::BadChecksum),
+    };
+
+    let values = &data[..data.len() - 6];
+    let (version, program) = (values[0], &values[1..]);
+    if version > 16 {
+        return Err(BtcAddressError::InvalidWitnessVersion(version));
+    }
+    let expected = if version == 0 {
+        Bech32Variant::Bech32
+    } else {
+        Bech32Variant::Bech32m
+    };
+    if variant != expected {
+        return Err(BtcAddressError::WrongChecksumVariant { version, expected });
+    }
+
+    let program = convert_5_to_8_bits(program)?;
+    let address_type = match (version, program.len()) {
+        (0, 20) => BtcAddressType::P2wpkh,
+        (0, 32) => BtcAddressType::P2wsh,
+        (0, len) => return Err(BtcAddressError::InvalidProgramLength { version, len }),
+        (_, len) if !(2..=40).contains(&len) => {
+            return Err(BtcAddressError::InvalidProgramLength { version, len });
+        }
+        (1, 32) => BtcAddressType::P2tr,
+        (version, _) => BtcAddressType::Witness(version),
+    };
+
+    Ok(BtcAddress {
+        address: s.to_ascii_lowercase(),
+        network,
+        address_type,
+    })
+}
+
+fn convert_5_to_8_bits(data: &[u8]) -> Result<Vec<u8>, BtcAddressError> {
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    let mut out = Vec::with_capacity(data.len() * 5 / 8);
+    for &value in data {
+        acc = ((acc << 5) | value as u32) & 0xfff;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    // Leftover bits must be fewer than 5 and all zero.
+    if bits >= 5 || (acc << (8 - bits)) & 0xff != 0 {
+        return Err(BtcAddressError::InvalidPadding);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Result<BtcAddress, BtcAddressError> {
+        s.parse()
+    }
+
+    #[test]
+    fn accepts_base58check_addresses() {
+        let p2pkh = parse("1BoatSLRHtKNngkdXEeobR76b53LETtpyT").unwrap();
+        assert_eq!(p2pkh.address_type(), BtcAddressType::P2pkh);
+        assert_eq!(p2pkh.network(), Network::Mainnet);
+
+        let p2sh = parse("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy").unwrap();
+        assert_eq!(p2sh.address_type(), BtcAddressType::P2sh);
+
+        let testnet = parse("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn").unwrap();
+        assert_eq!(testnet.network(), Network::Testnet);
+    }
+
+    #[test]
+    fn accepts_segwit_addresses() {
+        let v0 = parse("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").unwrap();
+        assert_eq!(v0.address_type(), BtcAddressType::P2wpkh);
+
+        let upper = parse("BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4").unwrap();
+        assert_eq!(upper, v0);
+
+        let taproot =
+            parse("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0").unwrap();
+        assert_eq!(taproot.address_type(), BtcAddressType::P2tr);
+
+        let testnet =
+            parse("tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7").unwrap();
+        assert_eq!(testnet.network(), Network::Testnet);
+        assert_eq!(testnet.address_type(), BtcAddressType::P2wsh);
+    }
+
+    #[test]
+    fn rejects_bad_base58check() {
+        assert_eq!(
+            parse("1BoatSLRHtKNngkdXEeobR76b53LETtpzz"),
+            Err(BtcAddressError::BadChecksum)
+        );
+        assert_eq!(
+            parse("1BoatSLRHtKNngkdXEeobR76b53LETtpy0"),
+            Err(BtcAddressError::InvalidCharacter { ch: '0', pos: 33 })
+        );
+        assert_eq!(
+            parse("1Boat"),
+            Err(BtcAddressError::InvalidPayloadLength(4))
+        );
+        assert_eq!(parse(""), Err(BtcAddressError::Empty));
+    }
+
+    #[test]
+    fn rejects_bad_segwit() {
+        assert_eq!(
+            parse("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt08O"),
+            Err(BtcAddressError::InvalidCharacter { ch: 'O', pos: 41 })
+        );
+        assert_eq!(
+            parse("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5"),
+            Err(BtcAddressError::BadChecksum)
+        );
+        assert_eq!(
+            parse("bc1QW508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"),
+            Err(BtcAddressError::MixedCase)
+        );
+        assert_eq!(parse("bc1qw508d6"), Err(BtcAddressError::InvalidLength(10)));
+        // v0 program encoded with bech32m
+        assert_eq!(
+            parse("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kemeawh"),
+            Err(BtcAddressError::WrongChecksumVariant {
+                version: 0,
+                expected: Bech32Variant::Bech32,
+            })
+        );
+        // v1 program encoded with bech32
+        assert_eq!(
+            parse("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd"),
+            Err(BtcAddressError::WrongChecksumVariant {
+                version: 1,
+                expected: Bech32Variant::Bech32m,
+            })
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use std::fmt;
+use std::str::FromStr;
+
+use sha3::{Digest, Keccak256};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum EthAddressError {
+    #[error("missing 0x prefix")]
+    MissingPrefix,
+    #[error("invalid length: expected 40 hex digits, got {0} bytes")]
+    InvalidLength(usize),
+    #[error("invalid hex character '{ch}' at position {pos}")]
+    InvalidHexCharacter { ch: char, pos: usize },
+    #[error("EIP-55 checksum mismatch, expected {expected}")]
+    ChecksumMismatch { address: String, expected: String },
+}
+
+/// A 20 byte Ethereum address. Mixed-case input must carry a valid EIP-55 checksum,
+/// all-lowercase and all-uppercase input is accepted as unchecksummed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EthAddress([u8; 20]);
+
+impl EthAddress {
+    pub fn to_checksum_string(self) -> String {
+        let lower: String = self.0.iter().map(|b| format!("{:02x}", b)).collect();
+        let hash = Keccak256::digest(lower.as_bytes());
+
+        let mut out = String::with_capacity(42);
+        out.push_str("0x");
+        for (i, c) in lower.chars().enumerate() {
+            let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+            if nibble >= 8 {
+                out.push(c.to_ascii_uppercase());
+            } else {
+                out.push(c);
+            }
+        }
+        out
+    }
+}
+
+impl fmt::Display for EthAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_checksum_string())
+    }
+}
+
+impl FromStr for EthAddress {
+    type Err = EthAddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix("0x").ok_or(EthAddressError::MissingPrefix)?;
+        if hex.len() != 40 {
+            return Err(EthAddressError::InvalidLength(hex.len()));
+        }
+
+        let mut bytes = [0u8; 20];
+        for (i, ch) in hex.chars().enumerate() {
+            let digit = ch
+                .to_digit(16)
+                .ok_or(EthAddressError::InvalidHexCharacter { ch, pos: i + 2 })?;
+            bytes[i / 2] |= (digit as u8) << (if i % 2 == 0 { 4 } else { 0 });
+        }
+        let address = EthAddress(bytes);
+
+        let has_lower = hex.chars().any(|c| c.is_ascii_lowercase());
+        let has_upper = hex.chars().any(|c| c.is_ascii_uppercase());
+        if has_lower && has_upper {
+            let expected = address.to_checksum_string();
+            if expected != s {
+                return Err(EthAddressError::ChecksumMismatch {
+                    address: s.to_string(),
+                    expected,
+                });
+            }
+        }
+        Ok(address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vectors from EIP-55
+    const CHECKSUMMED: [&str; 4] = [
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ];
+
+    #[test]
+    fn accepts_and_produces_eip55_checksums() {
+        for s in CHECKSUMMED {
+            let address: EthAddress = s.parse().unwrap();
+            assert_eq!(address.to_checksum_string(), s);
+        }
+        let upper: EthAddress = "0x52908400098527886E0F7030069857D2E4169EE7"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            upper.to_checksum_string(),
+            "0x52908400098527886E0F7030069857D2E4169EE7"
+        );
+    }
+
+    #[test]
+    fn accepts_unchecksummed_lower_and_upper_case() {
+        let lower: EthAddress = "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"
+            .parse()
+            .unwrap();
+        let upper: EthAddress = "0x5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED"
+            .parse()
+            .unwrap();
+        assert_eq!(lower, upper);
+        assert_eq!(lower.to_string(), CHECKSUMMED[0]);
+    }
+
+    #[test]
+    fn rejects_mistyped_checksum() {
+        assert_eq!(
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD".parse::<EthAddress>(),
+            Err(EthAddressError::ChecksumMismatch {
+                address: "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD".to_string(),
+                expected: CHECKSUMMED[0].to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_addresses() {
+        assert_eq!(
+            "742d35Cc6634C0532925a3b844Bc454e4438f44e".parse::<EthAddress>(),
+            Err(EthAddressError::MissingPrefix)
+        );
+        assert_eq!(
+            "0x742d35cc6634c0532925a3b844bc454e4438f44".parse::<EthAddress>(),
+            Err(EthAddressError::InvalidLength(39))
+        );
+        // Non-ASCII input is measured in bytes, like the length check.
+        assert_eq!(
+            "0x742d35cc6634c0532925a3b844bc454e4438f44é".parse::<EthAddress>(),
+            Err(EthAddressError::InvalidLength(41))
+        );
+        assert_eq!(
+            "0xZZZZ35Cc6634C0532925a3b844Bc454e4438f44e".parse::<EthAddress>(),
+            Err(EthAddressError::InvalidHexCharacter { ch: 'Z', pos: 2 })
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use super::base58;
+
+const KNOWN_PROGRAMS: [(&str, &str); 6] = [
+    ("11111111111111111111111111111111", "System Program"),
+    (
+        "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+        "Token Program",
+    ),
+    (
+        "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL",
+        "Associated Token Program",
+    ),
+    (
+        "Stake11111111111111111111111111111111111111",
+        "Stake Program",
+    ),
+    (
+        "Vote111111111111111111111111111111111111111",
+        "Vote Program",
+    ),
+    (
+        "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr",
+        "Memo Program",
+    ),
+];
+
+#[derive(Debug, Error, PartialEq)]
+pub enum SolAddressError {
+    #[error("address is empty")]
+    Empty,
+    #[error("invalid base58 character '{ch}' at position {pos}")]
+    InvalidCharacter { ch: char, pos: usize },
+    #[error("invalid length: expected 32 bytes, decoded {0}")]
+    InvalidLength(usize),
+}
+
+/// A Solana public key, the base58 encoding of exactly 32 bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolAddress {
+    address: String,
+}
+
+impl SolAddress {
+    /// Returns the name of a well-known program if this address is one. Sends to a
+    /// program ID are valid but almost never what a user intends.
+    pub fn known_program(&self) -> Option<&'static str> {
+        KNOWN_PROGRAMS
+            .iter()
+            .find(|(id, _)| *id == self.address)
+            .map(|(_, name)| *name)
+    }
+}
+
+impl fmt::Display for SolAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.address)
+    }
+}
+
+impl FromStr for SolAddress {
+    type Err = SolAddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(SolAddressError::Empty);
+        }
+        let decoded = base58::decode(s).map_err(|e| SolAddressError::InvalidCharacter {
+            ch: e.ch,
+            pos: e.pos,
+        })?;
+        if decoded.len() != 32 {
+            return Err(SolAddressError::InvalidLength(decoded.len()));
+        }
+        Ok(SolAddress {
+            address: s.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_32_byte_keys() {
+        let system: SolAddress = "11111111111111111111111111111111".parse().unwrap();
+        assert_eq!(system.known_program(), Some("System Program"));
+
+        let user: SolAddress = "4Nd1mY8iZP2Yvn6tA8FWiVvXqUuY1eWYQfFCJ2yJcQrC"
+            .parse()
+            .unwrap();
+        assert_eq!(user.known_program(), None);
+    }
+
+    #[test]
+    fn known_program_ids_are_valid_keys() {
+        for (id, name) in KNOWN_PROGRAMS {
+            let address: SolAddress = id.parse().unwrap();
+            assert_eq!(address.known_program(), Some(name));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_keys() {
+        assert_eq!("".parse::<SolAddress>(), Err(SolAddressError::Empty));
+        assert_eq!(
+            "4Nd1mY8iZP2Yvn6tA8FWiVvXqUuY1eWYQfFCJ2yJcQr0".parse::<SolAddress>(),
+            Err(SolAddressError::InvalidCharacter { ch: '0', pos: 43 })
+        );
+        assert_eq!(
+            "GARBAGEADDRESS123456789".parse::<SolAddress>(),
+            Err(SolAddressError::InvalidLength(17))
+        );
+        assert_eq!(
+            "4Nd1mY8iZP2Yvn6tA8FWiVvXqUuY1eWYQfFCJ2yJcQr".parse::<SolAddress>(),
+            Err(SolAddressError::InvalidLength(31))
+        );
+    }
+}
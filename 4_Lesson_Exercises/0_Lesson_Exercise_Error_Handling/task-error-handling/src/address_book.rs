@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ModernTxError;
+use crate::address::{Address, Network};
+use crate::amount::Asset;
+use crate::store::StorageError;
+
+/// A validated address saved under a label, tagged with the network it belongs to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub asset: Asset,
+    pub network: Network,
+    pub address: Address,
+}
+
+// The on-disk form of an entry. Addresses are stored as text and validated again on load.
+#[derive(Serialize, Deserialize)]
+struct StoredEntry {
+    currency: Asset,
+    network: Network,
+    address: String,
+}
+
+/// Addresses by label, so scripts can say `send eth 2 to alice`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AddressBook {
+    entries: BTreeMap<String, Entry>,
+}
+
+impl AddressBook {
+    pub fn new() -> Self {
+        AddressBook::default()
+    }
+
+    /// Validates `address` for `asset` and saves it under `label`, replacing any previous
+    /// entry. Labels are single words other than `address`, so scripts can refer to them.
+    /// BTC addresses encode their network, which has to agree with `network`.
+    pub fn insert(
+        &mut self,
+        label: &str,
+        asset: Asset,
+        network: Network,
+        address: &str,
+    ) -> Result<(), ModernTxError> {
+        if label.is_empty() || label == "address" || label.contains(char::is_whitespace) {
+            return Err(ModernTxError::InvalidFormat);
+        }
+        let address = Address::parse(asset, address)?;
+        if let Some(found) = address.network()
+            && found != network
+        {
+            return Err(ModernTxError::NetworkMismatch {
+                address: address.to_string(),
+                expected: network,
+                found,
+            });
+        }
+        self.entries.insert(
+            label.to_string(),
+            Entry {
+                asset,
+                network,
+                address,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn get(&self, label: &str) -> Option<&Entry> {
+        self.entries.get(label)
+    }
+
+    /// Reads a JSON address book written by `save`, validating every entry again.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ModernTxError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|source| StorageError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let stored: BTreeMap<String, StoredEntry> =
+            serde_json::from_str(&contents).map_err(|e| StorageError::CorruptRecord {
+                path: path.to_path_buf(),
+                line: e.line(),
+                reason: e.to_string(),
+            })?;
+
+        let mut book = AddressBook::new();
+        for (label, entry) in stored {
+            book.insert(&label, entry.currency, entry.network, &entry.address)?;
+        }
+        Ok(book)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), StorageError> {
+        let path = path.as_ref();
+        let stored: BTreeMap<&str, StoredEntry> = self
+            .entries
+            .iter()
+            .map(|(label, entry)| {
+                let stored = StoredEntry {
+                    currency: entry.asset,
+                    network: entry.network,
+                    address: entry.address.to_string(),
+                };
+                (label.as_str(), stored)
+            })
+            .collect();
+        // Serializing string-keyed maps of plain fields cannot fail.
+        let json = serde_json::to_string_pretty(&stored).expect("address book is serializable");
+        fs::write(path, json + "\n").map_err(|source| StorageError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ETH: &str = "0x52908400098527886E0F7030069857D2E4169EE7";
+    const TESTNET_BTC: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+    #[test]
+    fn validates_entries() {
+        let mut book = AddressBook::new();
+        book.insert("alice", Asset::Eth, Network::Mainnet, ETH)
+            .unwrap();
+        assert_eq!(book.get("alice").unwrap().address.to_string(), ETH);
+
+        assert!(matches!(
+            book.insert("bob", Asset::Eth, Network::Mainnet, "0x1234"),
+            Err(ModernTxError::InvalidEthAddress(_))
+        ));
+        assert_eq!(
+            book.insert("two words", Asset::Eth, Network::Mainnet, ETH),
+            Err(ModernTxError::InvalidFormat)
+        );
+        assert_eq!(
+            book.insert("carol", Asset::Btc, Network::Mainnet, TESTNET_BTC),
+            Err(ModernTxError::NetworkMismatch {
+                address: TESTNET_BTC.to_string(),
+                expected: Network::Mainnet,
+                found: Network::Testnet,
+            })
+        );
+        assert_eq!(book.get("bob"), None);
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let mut book = AddressBook::new();
+        book.insert("alice", Asset::Eth, Network::Testnet, ETH)
+            .unwrap();
+        book.insert("carol", Asset::Btc, Network::Testnet, TESTNET_BTC)
+            .unwrap();
+
+        let path = std::env::temp_dir().join(format!("address-book-{}.json", std::process::id()));
+        book.save(&path).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("\"currency\": \"eth\""));
+        assert_eq!(AddressBook::load(&path).unwrap(), book);
+
+        fs::write(&path, contents.replace(ETH, "0x1234")).unwrap();
+        assert!(matches!(
+            AddressBook::load(&path),
+            Err(ModernTxError::InvalidEthAddress(_))
+        ));
+        fs::remove_file(&path).unwrap();
+    }
+}
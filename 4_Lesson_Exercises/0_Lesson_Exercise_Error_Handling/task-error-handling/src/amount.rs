@@ -0,0 +1,209 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ModernTxError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Asset {
+    Btc,
+    Eth,
+    Sol,
+}
+
+impl Asset {
+    pub const ALL: [Asset; 3] = [Asset::Btc, Asset::Eth, Asset::Sol];
+
+    /// Number of decimal places between one coin and its base unit (satoshi, wei, lamport).
+    pub fn decimals(self) -> u32 {
+        match self {
+            Asset::Btc => 8,
+            Asset::Eth => 18,
+            Asset::Sol => 9,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Asset::Btc => "btc",
+            Asset::Eth => "eth",
+            Asset::Sol => "sol",
+        }
+    }
+}
+
+impl fmt::Display for Asset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+impl FromStr for Asset {
+    type Err = ModernTxError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "btc" => Ok(Asset::Btc),
+            "eth" => Ok(Asset::Eth),
+            "sol" => Ok(Asset::Sol),
+            _ => Err(ModernTxError::UnsupportedCurrency(s.to_string())),
+        }
+    }
+}
+
+/// An exact amount of an asset, stored in base units so no float rounding can occur.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Amount {
+    asset: Asset,
+    base_units: u128,
+}
+
+impl Amount {
+    pub fn from_base_units(asset: Asset, base_units: u128) -> Self {
+        Amount { asset, base_units }
+    }
+
+    pub fn asset(&self) -> Asset {
+        self.asset
+    }
+
+    pub fn base_units(&self) -> u128 {
+        self.base_units
+    }
+
+    /// Parses a human readable decimal like "0.5" into base units of `asset`.
+    pub fn parse_decimal(asset: Asset, value: &str) -> Result<Self, ModernTxError> {
+        let invalid = || ModernTxError::InvalidAmount(value.to_string());
+
+        let (int_part, frac_part) = value.split_once('.').unwrap_or((value, ""));
+        if int_part.is_empty() && frac_part.is_empty() {
+            return Err(invalid());
+        }
+        let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if !all_digits(int_part) || !all_digits(frac_part) {
+            return Err(invalid());
+        }
+        if frac_part.len() > asset.decimals() as usize {
+            return Err(invalid());
+        }
+
+        let scale = 10u128.pow(asset.decimals());
+        let mut base_units: u128 = 0;
+        for digit in int_part.bytes() {
+            base_units = base_units
+                .checked_mul(10)
+                .and_then(|v| v.checked_add((digit - b'0') as u128))
+                .ok_or(ModernTxError::Overflow)?;
+        }
+        base_units = base_units
+            .checked_mul(scale)
+            .ok_or(ModernTxError::Overflow)?;
+
+        let mut frac_scale = scale;
+        for digit in frac_part.bytes() {
+            frac_scale /= 10;
+            base_units = base_units
+                .checked_add((digit - b'0') as u128 * frac_scale)
+                .ok_or(ModernTxError::Overflow)?;
+        }
+        Ok(Amount { asset, base_units })
+    }
+
+    /// The amount as a plain decimal without trailing zeros or currency, e.g. "0.5".
+    pub fn to_decimal(self) -> String {
+        let scale = 10u128.pow(self.asset.decimals());
+        let int_part = self.base_units / scale;
+        let frac_part = self.base_units % scale;
+        if frac_part == 0 {
+            return int_part.to_string();
+        }
+        let frac = format!(
+            "{:0width$}",
+            frac_part,
+            width = self.asset.decimals() as usize
+        );
+        format!("{}.{}", int_part, frac.trim_end_matches('0'))
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.to_decimal(), self.asset)
+    }
+}
+
+impl FromStr for Amount {
+    type Err = ModernTxError;
+
+    /// Parses "<decimal> <currency>", e.g. "0.5 btc".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(value), Some(currency), None) => Amount::parse_decimal(currency.parse()?, value),
+            _ => Err(ModernTxError::InvalidAmount(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_into_base_units() {
+        let half: Amount = "0.5 btc".parse().unwrap();
+        assert_eq!(half.base_units(), 50_000_000);
+
+        let one_wei: Amount = "0.000000000000000001 ETH".parse().unwrap();
+        assert_eq!(one_wei.asset(), Asset::Eth);
+        assert_eq!(one_wei.base_units(), 1);
+
+        let lamports: Amount = "2.000000001 sol".parse().unwrap();
+        assert_eq!(lamports.base_units(), 2_000_000_001);
+    }
+
+    #[test]
+    fn round_trips_exactly() {
+        for s in [
+            "0.5 btc",
+            "10 eth",
+            "0.1 eth",
+            "1.23456789 btc",
+            "0.000000001 sol",
+        ] {
+            let amount: Amount = s.parse().unwrap();
+            assert_eq!(amount.to_string(), s);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_amounts() {
+        assert_eq!(
+            "0.000000001 btc".parse::<Amount>(),
+            Err(ModernTxError::InvalidAmount("0.000000001".to_string()))
+        );
+        assert_eq!(
+            "-1 btc".parse::<Amount>(),
+            Err(ModernTxError::InvalidAmount("-1".to_string()))
+        );
+        assert_eq!(
+            ". btc".parse::<Amount>(),
+            Err(ModernTxError::InvalidAmount(".".to_string()))
+        );
+        assert_eq!(
+            "1 doge".parse::<Amount>(),
+            Err(ModernTxError::UnsupportedCurrency("doge".to_string()))
+        );
+        assert_eq!(
+            "1000000000000000000000000000000000000000 btc".parse::<Amount>(),
+            Err(ModernTxError::Overflow)
+        );
+        // The whole coins still fit, only the fraction pushes past u128::MAX.
+        assert_eq!(
+            "340282366920938463463.999999999999999999 eth".parse::<Amount>(),
+            Err(ModernTxError::Overflow)
+        );
+    }
+}
@@ -0,0 +1,44 @@
+use thiserror::Error;
+
+use crate::address::Address;
+use crate::amount::{Amount, Asset};
+
+pub mod mock;
+
+/// Failures reported by a node when talking to a chain.
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum BackendError {
+    #[error("request timed out")]
+    Timeout,
+    #[error("nonce too low: expected at least {expected}, got {got}")]
+    NonceTooLow { expected: u64, got: u64 },
+    #[error("transaction rejected: {0}")]
+    Rejected(String),
+    #[error("mempool is full")]
+    MempoolFull,
+    #[error("rate limited by the node")]
+    RateLimited,
+}
+
+/// A transfer from the wallet's account on the chain of `amount`'s asset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transfer {
+    pub amount: Amount,
+    pub to: Address,
+    pub fee: Amount,
+    pub nonce: u64,
+}
+
+/// The node operations a wallet needs to execute sends on a real chain.
+pub trait ChainBackend {
+    /// Submits the transfer and returns its transaction id.
+    fn broadcast(&mut self, transfer: &Transfer) -> Result<String, BackendError>;
+
+    /// The wallet's on-chain balance of `asset`.
+    fn get_balance(&self, asset: Asset) -> Result<Amount, BackendError>;
+
+    /// The nonce the next transfer of `asset` has to use.
+    fn get_nonce(&self, asset: Asset) -> Result<u64, BackendError>;
+
+    fn estimate_fee(&self, to: &Address) -> Result<Amount, BackendError>;
+}
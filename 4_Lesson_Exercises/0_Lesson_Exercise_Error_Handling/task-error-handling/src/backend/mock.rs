@@ -0,0 +1,94 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use sha2::{Digest, Sha256};
+
+use super::{BackendError, ChainBackend, Transfer};
+use crate::address::Address;
+use crate::amount::{Amount, Asset};
+use crate::fee::FeeModel;
+
+/// An in-memory chain for tests. Failures queued with `fail_next` are returned by the
+/// following broadcasts, one each, before any real processing happens.
+#[derive(Debug, Clone, Default)]
+pub struct MockBackend {
+    fees: FeeModel,
+    balances: BTreeMap<Asset, u128>,
+    nonces: BTreeMap<Asset, u64>,
+    failures: VecDeque<BackendError>,
+    broadcasts: Vec<Transfer>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        MockBackend::default()
+    }
+
+    pub fn with_balance(mut self, amount: Amount) -> Self {
+        *self.balances.entry(amount.asset()).or_insert(0) += amount.base_units();
+        self
+    }
+
+    pub fn fail_next(&mut self, err: BackendError) {
+        self.failures.push_back(err);
+    }
+
+    /// Every transfer the chain accepted, in order.
+    pub fn broadcasts(&self) -> &[Transfer] {
+        &self.broadcasts
+    }
+}
+
+impl ChainBackend for MockBackend {
+    fn broadcast(&mut self, transfer: &Transfer) -> Result<String, BackendError> {
+        if let Some(err) = self.failures.pop_front() {
+            return Err(err);
+        }
+
+        let asset = transfer.amount.asset();
+        let expected = self.get_nonce(asset)?;
+        if transfer.nonce < expected {
+            return Err(BackendError::NonceTooLow {
+                expected,
+                got: transfer.nonce,
+            });
+        }
+        let total = transfer
+            .amount
+            .base_units()
+            .checked_add(transfer.fee.base_units())
+            .ok_or_else(|| BackendError::Rejected("amount plus fee overflows".to_string()))?;
+        let balance = self.balances.entry(asset).or_insert(0);
+        *balance = balance
+            .checked_sub(total)
+            .ok_or_else(|| BackendError::Rejected("insufficient balance".to_string()))?;
+        self.nonces.insert(asset, transfer.nonce + 1);
+        self.broadcasts.push(transfer.clone());
+
+        let payload = format!(
+            "{}:{}:{}:{}",
+            asset,
+            transfer.amount.base_units(),
+            transfer.to,
+            transfer.nonce
+        );
+        Ok(Sha256::digest(payload.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect())
+    }
+
+    fn get_balance(&self, asset: Asset) -> Result<Amount, BackendError> {
+        let base_units = self.balances.get(&asset).copied().unwrap_or(0);
+        Ok(Amount::from_base_units(asset, base_units))
+    }
+
+    fn get_nonce(&self, asset: Asset) -> Result<u64, BackendError> {
+        Ok(self.nonces.get(&asset).copied().unwrap_or(0))
+    }
+
+    fn estimate_fee(&self, to: &Address) -> Result<Amount, BackendError> {
+        self.fees
+            .estimate(to)
+            .map_err(|e| BackendError::Rejected(e.to_string()))
+    }
+}
@@ -0,0 +1,215 @@
+use thiserror::Error;
+
+use crate::ModernTxError;
+use crate::address::Address;
+use crate::oracle::PriceOracle;
+use crate::script::{self, SendTx};
+use crate::wallet::Wallet;
+
+/// Every failure of a rejected atomic batch, keyed by the index of the send in the batch.
+#[derive(Debug, Error, PartialEq)]
+#[error("Batch rejected, {} of {total} sends failed", failures.len())]
+pub struct BatchError {
+    pub total: usize,
+    pub failures: Vec<(usize, ModernTxError)>,
+}
+
+/// Applies every send or none of them. The sends run in order against a scratch copy
+/// of the wallet, so later sends see the balance left by earlier ones, and all failures
+/// are collected before deciding whether to commit. A rejected batch still leaves its
+/// attempts in the wallet's history. `batch` identifies the batch, see `send_best_effort`.
+pub fn send_atomic(
+    wallet: &mut Wallet,
+    batch: &str,
+    sends: &[SendTx],
+    oracle: &impl PriceOracle,
+) -> Result<Vec<Address>, BatchError> {
+    let mut scratch = wallet.clone();
+    let mut addresses = Vec::with_capacity(sends.len());
+    let mut failures = Vec::new();
+    for (i, result) in send_best_effort(&mut scratch, batch, sends, oracle)
+        .into_iter()
+        .enumerate()
+    {
+        match result {
+            Ok(address) => addresses.push(address),
+            Err(err) => failures.push((i, err)),
+        }
+    }
+
+    if !failures.is_empty() {
+        let err = BatchError {
+            total: sends.len(),
+            failures,
+        };
+        wallet.keep_history_of(&scratch, &err.to_string());
+        return Err(err);
+    }
+    *wallet = scratch;
+    Ok(addresses)
+}
+
+/// Applies every send that can be applied and reports the outcome of each one. Sends
+/// are idempotent per position in `batch`, so retrying a batch under the same id skips
+/// the sends that went through, while a new batch needs a new id. Sends paid with
+/// another currency are converted through `oracle`, within the slippage limit of the
+/// prices the batch started with.
+pub fn send_best_effort(
+    wallet: &mut Wallet,
+    batch: &str,
+    sends: &[SendTx],
+    oracle: &impl PriceOracle,
+) -> Vec<Result<Address, ModernTxError>> {
+    let accepted = script::accept_prices(sends, oracle);
+    sends
+        .iter()
+        .enumerate()
+        .map(|(i, tx)| tx.execute(wallet, batch, i as u64, oracle, &accepted))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::Asset;
+    use crate::history::{self, Status};
+    use crate::oracle::table::StaticOracle;
+    use crate::oracle::{PayWith, Price};
+
+    const VALID_BTC: &str = "1BoatSLRHtKNngkdXEeobR76b53LETtpyT";
+    const BAD_CHECKSUM_BTC: &str = "1BoatSLRHtKNngkdXEeobR76b53LETtpzz";
+
+    fn send(amount: &str, address: &str) -> SendTx {
+        SendTx {
+            amount: amount.parse().unwrap(),
+            address: address.to_string(),
+            pay_with: None,
+            key: None,
+        }
+    }
+
+    fn wallet_with(amount: &str) -> Wallet {
+        let mut wallet = Wallet::new();
+        wallet.deposit(amount.parse().unwrap()).unwrap();
+        wallet
+    }
+
+    #[test]
+    fn atomic_batch_applies_all_sends() {
+        let mut wallet = wallet_with("10 btc");
+        let sends = [send("4 btc", VALID_BTC), send("5 btc", VALID_BTC)];
+        assert_eq!(
+            send_atomic(&mut wallet, "batch-1", &sends, &StaticOracle::new())
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(wallet.balance(Asset::Btc).to_string(), "0.9999712 btc");
+    }
+
+    #[test]
+    fn atomic_batch_reports_every_failure_and_changes_nothing() {
+        let mut wallet = wallet_with("10 btc");
+        let sends = [
+            send("4 btc", VALID_BTC),
+            send("1 btc", BAD_CHECKSUM_BTC),
+            send("5 btc", VALID_BTC),
+            send("2 btc", VALID_BTC),
+        ];
+        let err = send_atomic(&mut wallet, "batch-1", &sends, &StaticOracle::new()).unwrap_err();
+
+        assert_eq!(err.total, 4);
+        let failed: Vec<usize> = err.failures.iter().map(|(i, _)| *i).collect();
+        assert_eq!(failed, vec![1, 3]);
+        assert!(matches!(
+            err.failures[0].1,
+            ModernTxError::InvalidBtcAddress(_)
+        ));
+        assert_eq!(err.failures[1].1, ModernTxError::InsufficientFunds);
+        assert_eq!(err.to_string(), "Batch rejected, 2 of 4 sends failed");
+        assert_eq!(wallet.balance(Asset::Btc).to_string(), "10 btc");
+
+        // Every attempt is in the history, the rolled back ones as failed.
+        let history = wallet.history().entries();
+        assert_eq!(history.len(), 4);
+        assert!(history.iter().all(|e| e.status == Status::Failed));
+        assert_eq!(
+            history[0].error.as_deref(),
+            Some("Batch rejected, 2 of 4 sends failed")
+        );
+        assert_eq!(
+            history[3].error.as_deref(),
+            Some("Insufficient funds for transaction")
+        );
+    }
+
+    #[test]
+    fn best_effort_applies_valid_sends() {
+        let mut wallet = wallet_with("10 btc");
+        let sends = [
+            send("4 btc", VALID_BTC),
+            send("1 btc", BAD_CHECKSUM_BTC),
+            send("7 btc", VALID_BTC),
+            send("5.9 btc", VALID_BTC),
+        ];
+        let results = send_best_effort(&mut wallet, "batch-1", &sends, &StaticOracle::new());
+
+        let ok: Vec<bool> = results.iter().map(Result::is_ok).collect();
+        assert_eq!(ok, vec![true, false, false, true]);
+        assert_eq!(results[2], Err(ModernTxError::InsufficientFunds));
+        assert_eq!(wallet.balance(Asset::Btc).to_string(), "0.0999712 btc");
+    }
+
+    #[test]
+    fn retrying_a_batch_only_applies_the_missing_sends() {
+        let mut wallet = wallet_with("5 btc");
+        let sends = [send("4 btc", VALID_BTC), send("2 btc", VALID_BTC)];
+        let first = send_best_effort(&mut wallet, "batch-1", &sends, &StaticOracle::new());
+        assert_eq!(first[1], Err(ModernTxError::InsufficientFunds));
+
+        wallet.deposit("2 btc".parse().unwrap()).unwrap();
+        let retry = send_best_effort(&mut wallet, "batch-1", &sends, &StaticOracle::new());
+        assert!(retry.iter().all(Result::is_ok));
+        assert_eq!(wallet.balance(Asset::Btc).to_string(), "0.9999712 btc");
+    }
+
+    #[test]
+    fn converts_sends_paid_with_another_currency() {
+        let address = "0x52908400098527886E0F7030069857D2E4169EE7";
+        let mut oracle = StaticOracle::new();
+        oracle.insert(Price {
+            base: Asset::Eth,
+            rate: "150 sol".parse().unwrap(),
+            timestamp: history::now(),
+        });
+        let mut wallet = wallet_with("400 sol");
+        let paid_with = |max_cost: Option<&str>| SendTx {
+            pay_with: Some(PayWith {
+                asset: Asset::Sol,
+                max_cost: max_cost.map(|max_cost| max_cost.parse().unwrap()),
+            }),
+            ..send("2 eth", address)
+        };
+        let sends = [paid_with(None), paid_with(Some("300 sol"))];
+
+        let results = send_best_effort(&mut wallet, "batch-1", &sends, &oracle);
+        assert!(results[0].is_ok());
+        assert_eq!(
+            results[1],
+            Err(ModernTxError::CostLimitExceeded {
+                max_cost: "300 sol".parse().unwrap(),
+                actual: "300.063 sol".parse().unwrap(),
+            })
+        );
+        assert_eq!(wallet.balance(Asset::Sol).to_string(), "99.937 sol");
+    }
+
+    #[test]
+    fn separate_batches_with_the_same_send_both_apply() {
+        let mut wallet = wallet_with("5 btc");
+        let sends = [send("1 btc", VALID_BTC)];
+        assert!(send_best_effort(&mut wallet, "batch-1", &sends, &StaticOracle::new())[0].is_ok());
+        assert!(send_best_effort(&mut wallet, "batch-2", &sends, &StaticOracle::new())[0].is_ok());
+        assert_eq!(wallet.balance(Asset::Btc).to_string(), "2.9999712 btc");
+    }
+}
@@ -0,0 +1,143 @@
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::error::Error as StdError;
+use std::fmt::{self, Write};
+
+use crate::ModernTxError;
+
+type BoxError = Box<dyn StdError + Send + Sync + 'static>;
+
+/// An error wrapped with a human readable description of what was being done when it
+/// happened. The wrapped error stays reachable through `source()`, so the whole chain
+/// can be walked or downcast.
+// Implemented by hand: thiserror would treat the Backtrace field as a nightly-only provider.
+#[derive(Debug)]
+pub struct ContextError {
+    context: String,
+    source: BoxError,
+    // Only captured when RUST_BACKTRACE or RUST_LIB_BACKTRACE is set.
+    trace: Backtrace,
+}
+
+impl ContextError {
+    pub fn new(context: impl Into<String>, source: impl Into<BoxError>) -> Self {
+        ContextError {
+            context: context.into(),
+            source: source.into(),
+            trace: Backtrace::capture(),
+        }
+    }
+
+    /// The first `ModernTxError` in the chain, i.e. what went wrong without the context.
+    pub fn tx_error(&self) -> Option<&ModernTxError> {
+        let mut cause: Option<&(dyn StdError + 'static)> = Some(self);
+        while let Some(err) = cause {
+            if let Some(tx_err) = err.downcast_ref::<ModernTxError>() {
+                return Some(tx_err);
+            }
+            cause = err.source();
+        }
+        None
+    }
+
+    /// Renders the context and every cause on its own line, followed by the backtrace
+    /// captured closest to the failure if backtraces are enabled.
+    pub fn report(&self) -> String {
+        let mut out = self.context.clone();
+        let mut trace = &self.trace;
+        let mut cause = self.source();
+        if cause.is_some() {
+            out.push_str("\n\nCaused by:");
+        }
+        let mut i = 0;
+        while let Some(err) = cause {
+            let _ = write!(out, "\n    {}: {}", i, err);
+            if let Some(inner) = err.downcast_ref::<ContextError>() {
+                trace = &inner.trace;
+            }
+            cause = err.source();
+            i += 1;
+        }
+        if trace.status() == BacktraceStatus::Captured {
+            let _ = write!(out, "\n\nStack backtrace:\n{}", trace);
+        }
+        out
+    }
+}
+
+impl fmt::Display for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.context)
+    }
+}
+
+impl StdError for ContextError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+/// Adds `.context(...)` to any `Result` whose error can be boxed.
+pub trait TxContext<T> {
+    fn context(self, context: impl Into<String>) -> Result<T, ContextError>;
+
+    fn with_context<C: Into<String>>(self, f: impl FnOnce() -> C) -> Result<T, ContextError>;
+}
+
+impl<T, E> TxContext<T> for Result<T, E>
+where
+    E: StdError + Send + Sync + 'static,
+{
+    fn context(self, context: impl Into<String>) -> Result<T, ContextError> {
+        self.map_err(|err| ContextError::new(context, err))
+    }
+
+    fn with_context<C: Into<String>>(self, f: impl FnOnce() -> C) -> Result<T, ContextError> {
+        self.map_err(|err| ContextError::new(f(), err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SpecificTxError;
+
+    fn failing_send() -> Result<(), ContextError> {
+        Err::<(), _>(ModernTxError::from(SpecificTxError {
+            details: "tx details".to_string(),
+            currency: "USDT".to_string(),
+        }))
+        .context("while sending 1 btc to 1BoatSLRHtKNngkdXEeobR76b53LETtpyT")
+        .with_context(|| format!("line {} of {}", 3, "payouts.txt"))
+    }
+
+    #[test]
+    fn keeps_the_source_chain_intact() {
+        let err = failing_send().unwrap_err();
+        assert_eq!(err.to_string(), "line 3 of payouts.txt");
+
+        let inner = err.source().unwrap();
+        assert_eq!(
+            inner.to_string(),
+            "while sending 1 btc to 1BoatSLRHtKNngkdXEeobR76b53LETtpyT"
+        );
+
+        let tx_err = err.tx_error().unwrap();
+        assert!(matches!(tx_err, ModernTxError::SpecificTxError(_)));
+        assert_eq!(tx_err.source().unwrap().to_string(), "USDT: tx details");
+    }
+
+    #[test]
+    fn report_lists_every_cause() {
+        let report = failing_send().unwrap_err().report();
+        let without_backtrace = report.split("\n\nStack backtrace:").next().unwrap();
+        assert_eq!(
+            without_backtrace,
+            "line 3 of payouts.txt\n\
+             \n\
+             Caused by:\n    \
+             0: while sending 1 btc to 1BoatSLRHtKNngkdXEeobR76b53LETtpyT\n    \
+             1: Specific transaction error: USDT: tx details\n    \
+             2: USDT: tx details"
+        );
+    }
+}
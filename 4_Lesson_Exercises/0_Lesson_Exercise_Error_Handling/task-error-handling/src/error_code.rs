@@ -0,0 +1,431 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json::{Value, json};
+
+use crate::ModernTxError;
+use crate::backend::BackendError;
+
+/// Coarse grouping of errors, e.g. for mapping onto HTTP status codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// The request itself is wrong (400).
+    Validation,
+    /// The request is valid but the balance cannot cover it (402/409).
+    Funds,
+    /// The request is not allowed (403).
+    Auth,
+    /// A chain or node failed to process the request (502).
+    Upstream,
+    /// Our own infrastructure failed, e.g. the wallet store (500).
+    Internal,
+}
+
+impl ModernTxError {
+    /// Stable numeric code of the variant. Codes are part of the public API: never
+    /// renumber or reuse one, only append new codes for new variants.
+    pub fn code(&self) -> u16 {
+        match self {
+            ModernTxError::InvalidFormat => 1001,
+            ModernTxError::InvalidAddress => 1002,
+            ModernTxError::InvalidBtcAddress(_) => 1003,
+            ModernTxError::InvalidEthAddress(_) => 1004,
+            ModernTxError::EthChecksumMismatch { .. } => 1005,
+            ModernTxError::InvalidSolAddress(_) => 1006,
+            ModernTxError::InvalidAmount(_) => 1007,
+            ModernTxError::UnsupportedCurrency(_) => 1008,
+            ModernTxError::DuplicateTransaction { .. } => 1009,
+            ModernTxError::NetworkMismatch { .. } => 1010,
+            ModernTxError::LabelCurrencyMismatch { .. } => 1011,
+            ModernTxError::UnknownPendingSend { .. } => 1012,
+            ModernTxError::UnsupportedPair { .. } => 1013,
+            ModernTxError::InvalidMultisig { .. } => 1014,
+            ModernTxError::InsufficientFunds => 2001,
+            ModernTxError::Overflow => 2002,
+            ModernTxError::InsufficientFundsForFee { .. } => 2003,
+            ModernTxError::SlippageExceeded { .. } => 2004,
+            ModernTxError::CostLimitExceeded { .. } => 2005,
+            ModernTxError::Unauthorized { .. } => 3001,
+            ModernTxError::NotAnApprover { .. } => 3002,
+            ModernTxError::InvalidSignature { .. } => 3003,
+            ModernTxError::AlreadyApproved { .. } => 3004,
+            ModernTxError::ApprovalExpired { .. } => 3005,
+            ModernTxError::ApprovalRequired { .. } => 3006,
+            ModernTxError::NotEnoughApprovals { .. } => 3007,
+            ModernTxError::SpecificTxError(_) => 4001,
+            ModernTxError::BackendError(err) => match err {
+                BackendError::Timeout => 4002,
+                BackendError::NonceTooLow { .. } => 4003,
+                BackendError::Rejected(_) => 4004,
+                BackendError::MempoolFull => 4005,
+                BackendError::RateLimited => 4006,
+            },
+            ModernTxError::StalePrice { .. } => 4007,
+            ModernTxError::StorageError(_) => 5001,
+        }
+    }
+
+    pub fn category(&self) -> ErrorCategory {
+        match self.code() {
+            1000..=1999 => ErrorCategory::Validation,
+            2000..=2999 => ErrorCategory::Funds,
+            3000..=3999 => ErrorCategory::Auth,
+            4000..=4999 => ErrorCategory::Upstream,
+            _ => ErrorCategory::Internal,
+        }
+    }
+
+    /// Whether sending the same request again may succeed without changing it. A node
+    /// that rejected a transaction will reject it again.
+    pub fn retryable(&self) -> bool {
+        self.category() == ErrorCategory::Upstream
+            && !matches!(self, ModernTxError::BackendError(BackendError::Rejected(_)))
+    }
+
+    /// Structured fields of the variant, an empty object for unit variants.
+    pub fn details(&self) -> Value {
+        match self {
+            ModernTxError::SpecificTxError(err) => json!(err),
+            ModernTxError::InvalidBtcAddress(err) => json!({ "reason": err.to_string() }),
+            ModernTxError::InvalidEthAddress(err) => json!({ "reason": err.to_string() }),
+            ModernTxError::InvalidSolAddress(err) => json!({ "reason": err.to_string() }),
+            ModernTxError::StorageError(err) => json!({ "reason": err.to_string() }),
+            ModernTxError::BackendError(BackendError::NonceTooLow { expected, got }) => {
+                json!({ "expected": expected, "got": got })
+            }
+            ModernTxError::BackendError(BackendError::Rejected(reason)) => {
+                json!({ "reason": reason })
+            }
+            ModernTxError::EthChecksumMismatch { address, expected } => {
+                json!({ "address": address, "expected": expected })
+            }
+            ModernTxError::InvalidAmount(amount) => json!({ "amount": amount }),
+            ModernTxError::UnsupportedCurrency(currency) => json!({ "currency": currency }),
+            ModernTxError::DuplicateTransaction { key } => json!({ "key": key }),
+            ModernTxError::NetworkMismatch {
+                address,
+                expected,
+                found,
+            } => json!({ "address": address, "expected": expected, "found": found }),
+            ModernTxError::LabelCurrencyMismatch {
+                label,
+                expected,
+                found,
+            } => json!({ "label": label, "expected": expected, "found": found }),
+            ModernTxError::Unauthorized {
+                rule,
+                limit,
+                attempted,
+            } => json!({ "rule": rule, "limit": limit, "attempted": attempted }),
+            ModernTxError::ApprovalRequired { threshold } => {
+                json!({ "threshold": threshold.to_string() })
+            }
+            ModernTxError::UnknownPendingSend { id } | ModernTxError::ApprovalExpired { id } => {
+                json!({ "id": id })
+            }
+            ModernTxError::NotAnApprover { key } | ModernTxError::InvalidSignature { key } => {
+                json!({ "key": key })
+            }
+            ModernTxError::AlreadyApproved { id, key } => json!({ "id": id, "key": key }),
+            ModernTxError::NotEnoughApprovals {
+                id,
+                required,
+                approvals,
+            } => json!({ "id": id, "required": required, "approvals": approvals }),
+            ModernTxError::InvalidMultisig {
+                required,
+                approvers,
+            } => json!({ "required": required, "approvers": approvers }),
+            ModernTxError::UnsupportedPair { base, quote } => {
+                json!({ "base": base, "quote": quote })
+            }
+            ModernTxError::StalePrice {
+                base,
+                quote,
+                age,
+                max_age,
+            } => json!({ "base": base, "quote": quote, "age": age, "max_age": max_age }),
+            ModernTxError::SlippageExceeded {
+                quoted,
+                actual,
+                max_bps,
+            } => json!({
+                "quoted": quoted.to_string(),
+                "actual": actual.to_string(),
+                "max_bps": max_bps,
+            }),
+            ModernTxError::CostLimitExceeded { max_cost, actual } => json!({
+                "max_cost": max_cost.to_string(),
+                "actual": actual.to_string(),
+            }),
+            ModernTxError::InsufficientFundsForFee {
+                amount,
+                fee,
+                available,
+            } => json!({
+                "amount": amount.to_string(),
+                "fee": fee.to_string(),
+                "available": available.to_string(),
+            }),
+            ModernTxError::InvalidFormat
+            | ModernTxError::InvalidAddress
+            | ModernTxError::InsufficientFunds
+            | ModernTxError::Overflow
+            | ModernTxError::BackendError(
+                BackendError::Timeout | BackendError::MempoolFull | BackendError::RateLimited,
+            ) => {
+                json!({})
+            }
+        }
+    }
+}
+
+/// Serializes as `{code, category, message, details}` for API responses.
+impl Serialize for ModernTxError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ModernTxError", 4)?;
+        state.serialize_field("code", &self.code())?;
+        state.serialize_field("category", &self.category())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("details", &self.details())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::SpecificTxError;
+    use crate::address::Network;
+    use crate::address::btc::BtcAddressError;
+    use crate::address::eth::EthAddressError;
+    use crate::address::sol::SolAddressError;
+    use crate::amount::Asset;
+    use crate::policy::PolicyRule;
+    use crate::store::StorageError;
+
+    pub(crate) fn one_of_each() -> Vec<ModernTxError> {
+        vec![
+            ModernTxError::InvalidFormat,
+            ModernTxError::InsufficientFunds,
+            ModernTxError::Unauthorized {
+                rule: PolicyRule::DailyLimit,
+                limit: "1 btc".to_string(),
+                attempted: "2 btc".to_string(),
+            },
+            ModernTxError::SpecificTxError(SpecificTxError {
+                details: "tx details".to_string(),
+                currency: "USDT".to_string(),
+            }),
+            ModernTxError::InvalidAddress,
+            ModernTxError::InvalidBtcAddress(BtcAddressError::BadChecksum),
+            ModernTxError::InvalidEthAddress(EthAddressError::MissingPrefix),
+            ModernTxError::EthChecksumMismatch {
+                address: "0xabc".to_string(),
+                expected: "0xABC".to_string(),
+            },
+            ModernTxError::InvalidSolAddress(SolAddressError::Empty),
+            ModernTxError::InvalidAmount("x".to_string()),
+            ModernTxError::UnsupportedCurrency("doge".to_string()),
+            ModernTxError::Overflow,
+            ModernTxError::InsufficientFundsForFee {
+                amount: "1 sol".parse().unwrap(),
+                fee: "0.000005 sol".parse().unwrap(),
+                available: "1 sol".parse().unwrap(),
+            },
+            ModernTxError::StorageError(StorageError::CorruptRecord {
+                path: "journal".into(),
+                line: 1,
+                reason: "checksum mismatch".to_string(),
+            }),
+            ModernTxError::DuplicateTransaction {
+                key: "payout-7".to_string(),
+            },
+            ModernTxError::NetworkMismatch {
+                address: "tb1q".to_string(),
+                expected: Network::Mainnet,
+                found: Network::Testnet,
+            },
+            ModernTxError::LabelCurrencyMismatch {
+                label: "alice".to_string(),
+                expected: Asset::Btc,
+                found: Asset::Eth,
+            },
+            ModernTxError::UnknownPendingSend { id: 1 },
+            ModernTxError::InvalidMultisig {
+                required: 3,
+                approvers: 2,
+            },
+            ModernTxError::NotAnApprover {
+                key: "ab12".to_string(),
+            },
+            ModernTxError::InvalidSignature {
+                key: "ab12".to_string(),
+            },
+            ModernTxError::AlreadyApproved {
+                id: 1,
+                key: "ab12".to_string(),
+            },
+            ModernTxError::ApprovalExpired { id: 1 },
+            ModernTxError::ApprovalRequired {
+                threshold: "10 sol".parse().unwrap(),
+            },
+            ModernTxError::NotEnoughApprovals {
+                id: 1,
+                required: 2,
+                approvals: 1,
+            },
+            ModernTxError::UnsupportedPair {
+                base: Asset::Eth,
+                quote: Asset::Btc,
+            },
+            ModernTxError::StalePrice {
+                base: Asset::Eth,
+                quote: Asset::Sol,
+                age: 301,
+                max_age: 300,
+            },
+            ModernTxError::SlippageExceeded {
+                quoted: "300 sol".parse().unwrap(),
+                actual: "310 sol".parse().unwrap(),
+                max_bps: 100,
+            },
+            ModernTxError::CostLimitExceeded {
+                max_cost: "300 sol".parse().unwrap(),
+                actual: "310 sol".parse().unwrap(),
+            },
+            ModernTxError::BackendError(BackendError::Timeout),
+            ModernTxError::BackendError(BackendError::NonceTooLow {
+                expected: 2,
+                got: 1,
+            }),
+            ModernTxError::BackendError(BackendError::Rejected("bad signature".to_string())),
+            ModernTxError::BackendError(BackendError::MempoolFull),
+            ModernTxError::BackendError(BackendError::RateLimited),
+        ]
+    }
+
+    const VARIANTS: usize = 34;
+
+    // Lists every variant without a wildcard, so a new variant does not compile until
+    // it gets the next index here, and fails `one_of_each_covers_every_variant` until
+    // `one_of_each` has an example of it.
+    fn variant_index(err: &ModernTxError) -> usize {
+        match err {
+            ModernTxError::InvalidFormat => 0,
+            ModernTxError::InsufficientFunds => 1,
+            ModernTxError::Unauthorized { .. } => 2,
+            ModernTxError::SpecificTxError(_) => 3,
+            ModernTxError::InvalidAddress => 4,
+            ModernTxError::InvalidBtcAddress(_) => 5,
+            ModernTxError::InvalidEthAddress(_) => 6,
+            ModernTxError::EthChecksumMismatch { .. } => 7,
+            ModernTxError::InvalidSolAddress(_) => 8,
+            ModernTxError::InvalidAmount(_) => 9,
+            ModernTxError::UnsupportedCurrency(_) => 10,
+            ModernTxError::Overflow => 11,
+            ModernTxError::InsufficientFundsForFee { .. } => 12,
+            ModernTxError::StorageError(_) => 13,
+            ModernTxError::DuplicateTransaction { .. } => 14,
+            ModernTxError::BackendError(BackendError::Timeout) => 15,
+            ModernTxError::BackendError(BackendError::NonceTooLow { .. }) => 16,
+            ModernTxError::BackendError(BackendError::Rejected(_)) => 17,
+            ModernTxError::BackendError(BackendError::MempoolFull) => 18,
+            ModernTxError::BackendError(BackendError::RateLimited) => 19,
+            ModernTxError::NetworkMismatch { .. } => 20,
+            ModernTxError::LabelCurrencyMismatch { .. } => 21,
+            ModernTxError::ApprovalRequired { .. } => 22,
+            ModernTxError::UnknownPendingSend { .. } => 23,
+            ModernTxError::NotAnApprover { .. } => 24,
+            ModernTxError::InvalidSignature { .. } => 25,
+            ModernTxError::AlreadyApproved { .. } => 26,
+            ModernTxError::ApprovalExpired { .. } => 27,
+            ModernTxError::NotEnoughApprovals { .. } => 28,
+            ModernTxError::UnsupportedPair { .. } => 29,
+            ModernTxError::StalePrice { .. } => 30,
+            ModernTxError::SlippageExceeded { .. } => 31,
+            ModernTxError::InvalidMultisig { .. } => 32,
+            ModernTxError::CostLimitExceeded { .. } => 33,
+        }
+    }
+
+    #[test]
+    fn one_of_each_covers_every_variant() {
+        let mut indices: Vec<usize> = one_of_each().iter().map(variant_index).collect();
+        indices.sort();
+        assert_eq!(indices, (0..VARIANTS).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn codes_are_unique() {
+        let errors = one_of_each();
+        let codes: HashSet<u16> = errors.iter().map(ModernTxError::code).collect();
+        assert_eq!(codes.len(), errors.len());
+    }
+
+    #[test]
+    fn only_upstream_errors_are_retryable() {
+        let mut retryable: Vec<u16> = one_of_each()
+            .iter()
+            .filter(|err| err.retryable())
+            .map(ModernTxError::code)
+            .collect();
+        retryable.sort();
+        assert_eq!(retryable, vec![4001, 4002, 4003, 4005, 4006, 4007]);
+
+        for err in [
+            BackendError::Timeout,
+            BackendError::RateLimited,
+            BackendError::MempoolFull,
+        ] {
+            assert!(ModernTxError::BackendError(err).retryable());
+        }
+        for err in [
+            ModernTxError::BackendError(BackendError::Rejected("bad signature".to_string())),
+            ModernTxError::InsufficientFunds,
+            ModernTxError::InvalidAddress,
+            ModernTxError::SlippageExceeded {
+                quoted: "300 sol".parse().unwrap(),
+                actual: "310 sol".parse().unwrap(),
+                max_bps: 100,
+            },
+        ] {
+            assert!(!err.retryable(), "{}", err);
+        }
+        assert_eq!(
+            ModernTxError::InsufficientFunds.category(),
+            ErrorCategory::Funds
+        );
+    }
+
+    #[test]
+    fn serializes_nested_specific_tx_error_fields() {
+        let err = ModernTxError::SpecificTxError(SpecificTxError {
+            details: "tx details".to_string(),
+            currency: "USDT".to_string(),
+        });
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            json!({
+                "code": 4001,
+                "category": "upstream",
+                "message": "Specific transaction error: USDT: tx details",
+                "details": { "details": "tx details", "currency": "USDT" },
+            })
+        );
+    }
+
+    #[test]
+    fn serializes_unit_variants_with_empty_details() {
+        assert_eq!(
+            serde_json::to_value(ModernTxError::InsufficientFunds).unwrap(),
+            json!({
+                "code": 2001,
+                "category": "funds",
+                "message": "Insufficient funds for transaction",
+                "details": {},
+            })
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use crate::ModernTxError;
+use crate::address::Address;
+use crate::address::btc::BtcAddressType;
+use crate::amount::{Amount, Asset};
+
+// A 1-input, 2-output transaction spending a P2WPKH output and returning change to
+// P2WPKH: 10.5 vbytes of overhead, a 68 vbyte input and a 31 vbyte change output.
+const BTC_BASE_VBYTES: u64 = 110;
+
+/// Per-chain fee parameters. All fees are paid in the native asset of the chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeModel {
+    pub btc_sat_per_vbyte: u64,
+    pub eth_gas_limit: u64,
+    pub eth_gas_price_wei: u128,
+    pub sol_lamports_per_signature: u64,
+}
+
+impl Default for FeeModel {
+    fn default() -> Self {
+        FeeModel {
+            btc_sat_per_vbyte: 10,
+            // A plain ETH transfer always uses exactly 21000 gas.
+            eth_gas_limit: 21_000,
+            eth_gas_price_wei: 20_000_000_000,
+            sol_lamports_per_signature: 5_000,
+        }
+    }
+}
+
+impl FeeModel {
+    /// The fee for a single send to `address`, in the asset of its chain. Fee rates
+    /// too large to multiply out are an `Overflow`.
+    pub fn estimate(&self, address: &Address) -> Result<Amount, ModernTxError> {
+        let fee = match address {
+            Address::Btc(btc) => {
+                let vbytes = BTC_BASE_VBYTES + btc_output_vbytes(btc.address_type());
+                let sats = vbytes
+                    .checked_mul(self.btc_sat_per_vbyte)
+                    .ok_or(ModernTxError::Overflow)?;
+                Amount::from_base_units(Asset::Btc, u128::from(sats))
+            }
+            Address::Eth(_) => {
+                let wei = u128::from(self.eth_gas_limit)
+                    .checked_mul(self.eth_gas_price_wei)
+                    .ok_or(ModernTxError::Overflow)?;
+                Amount::from_base_units(Asset::Eth, wei)
+            }
+            // A transfer is signed by the sender only.
+            Address::Sol(_) => {
+                Amount::from_base_units(Asset::Sol, u128::from(self.sol_lamports_per_signature))
+            }
+        };
+        Ok(fee)
+    }
+}
+
+/// Size of the output paying to an address of the given type.
+fn btc_output_vbytes(address_type: BtcAddressType) -> u64 {
+    match address_type {
+        BtcAddressType::P2pkh => 34,
+        BtcAddressType::P2sh => 32,
+        BtcAddressType::P2wpkh => 31,
+        // Unknown witness versions are assumed to use 32 byte programs like v1.
+        BtcAddressType::P2wsh | BtcAddressType::P2tr | BtcAddressType::Witness(_) => 43,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fee(asset: Asset, address: &str) -> String {
+        let address = Address::parse(asset, address).unwrap();
+        FeeModel::default().estimate(&address).unwrap().to_string()
+    }
+
+    #[test]
+    fn btc_fee_depends_on_output_type() {
+        assert_eq!(
+            fee(Asset::Btc, "1BoatSLRHtKNngkdXEeobR76b53LETtpyT"),
+            "0.0000144 btc"
+        );
+        assert_eq!(
+            fee(Asset::Btc, "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"),
+            "0.0000141 btc"
+        );
+        assert_eq!(
+            fee(
+                Asset::Btc,
+                "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3"
+            ),
+            "0.0000153 btc"
+        );
+    }
+
+    #[test]
+    fn eth_and_sol_fees() {
+        assert_eq!(
+            fee(Asset::Eth, "0x52908400098527886E0F7030069857D2E4169EE7"),
+            "0.00042 eth"
+        );
+        assert_eq!(
+            fee(Asset::Sol, "11111111111111111111111111111111"),
+            "0.000005 sol"
+        );
+    }
+
+    #[test]
+    fn huge_fee_rates_overflow() {
+        let fees = FeeModel {
+            btc_sat_per_vbyte: u64::MAX,
+            eth_gas_price_wei: u128::MAX,
+            ..FeeModel::default()
+        };
+        for (asset, address) in [
+            (Asset::Btc, "1BoatSLRHtKNngkdXEeobR76b53LETtpyT"),
+            (Asset::Eth, "0x52908400098527886E0F7030069857D2E4169EE7"),
+        ] {
+            let address = Address::parse(asset, address).unwrap();
+            assert_eq!(fees.estimate(&address), Err(ModernTxError::Overflow));
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{Map, Value, json};
+
+use crate::amount::{Amount, Asset};
+use crate::wallet::Wallet;
+
+/// Seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Sent,
+    Failed,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Sent => write!(f, "sent"),
+            Status::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// One attempted send. `fee` is only known for completed sends, `error` only for failed ones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub timestamp: u64,
+    pub amount: Amount,
+    pub address: String,
+    pub status: Status,
+    pub fee: Option<Amount>,
+    pub error: Option<String>,
+}
+
+/// Every send a wallet attempted, in order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct History {
+    entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    pub fn record(&mut self, entry: HistoryEntry) {
+        self.entries.push(entry);
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    pub fn by_currency(&self, asset: Asset) -> impl Iterator<Item = &HistoryEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.amount.asset() == asset)
+    }
+
+    pub fn by_status(&self, status: Status) -> impl Iterator<Item = &HistoryEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.status == status)
+    }
+}
+
+/// The sends between two states of a wallet with the balances before and after, so the
+/// closing balance of each asset can be reconciled as opening minus amounts and fees.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub opening: Vec<Amount>,
+    pub closing: Vec<Amount>,
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl Statement {
+    /// `after` has to be `before` with more operations applied, e.g. a clone that ran
+    /// a script.
+    pub fn between(before: &Wallet, after: &Wallet) -> Self {
+        let seen = before.history().entries().len();
+        Statement {
+            opening: before.balances().collect(),
+            closing: after.balances().collect(),
+            entries: after.history().entries()[seen..].to_vec(),
+        }
+    }
+
+    /// One row per opening balance, send and closing balance, distinguished by `type`.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("type,timestamp,currency,amount,fee,address,status,error\n");
+        for balance in &self.opening {
+            csv.push_str(&balance_row("opening", balance));
+        }
+        for entry in &self.entries {
+            let fields = [
+                "send".to_string(),
+                entry.timestamp.to_string(),
+                entry.amount.asset().to_string(),
+                entry.amount.to_decimal(),
+                entry.fee.map(|fee| fee.to_decimal()).unwrap_or_default(),
+                entry.address.clone(),
+                entry.status.to_string(),
+                entry.error.clone().unwrap_or_default(),
+            ];
+            let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        for balance in &self.closing {
+            csv.push_str(&balance_row("closing", balance));
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> Value {
+        let balances = |amounts: &[Amount]| -> Map<String, Value> {
+            amounts
+                .iter()
+                .map(|a| (a.asset().to_string(), json!(a.to_decimal())))
+                .collect()
+        };
+        let transactions: Vec<Value> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                json!({
+                    "timestamp": entry.timestamp,
+                    "currency": entry.amount.asset(),
+                    "amount": entry.amount.to_decimal(),
+                    "fee": entry.fee.map(|fee| fee.to_decimal()),
+                    "address": entry.address,
+                    "status": entry.status.to_string(),
+                    "error": entry.error,
+                })
+            })
+            .collect();
+        json!({
+            "opening": balances(&self.opening),
+            "closing": balances(&self.closing),
+            "transactions": transactions,
+        })
+    }
+}
+
+fn balance_row(kind: &str, balance: &Amount) -> String {
+    format!(
+        "{},,{},{},,,,\n",
+        kind,
+        balance.asset(),
+        balance.to_decimal()
+    )
+}
+
+// Error texts may contain commas or quotes.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOL: &str = "11111111111111111111111111111111";
+
+    fn statement() -> Statement {
+        let mut wallet = Wallet::new();
+        wallet.deposit("3 sol".parse().unwrap()).unwrap();
+        let before = wallet.clone();
+        wallet.send("1 sol".parse().unwrap(), SOL).unwrap();
+        wallet.send("5 sol".parse().unwrap(), SOL).unwrap_err();
+        wallet
+            .send("1 btc".parse().unwrap(), "not,an address")
+            .unwrap_err();
+        Statement::between(&before, &wallet)
+    }
+
+    #[test]
+    fn records_every_attempt() {
+        let statement = statement();
+        let history = History {
+            entries: statement.entries,
+        };
+        let sent: Vec<String> = history
+            .by_status(Status::Sent)
+            .map(|e| e.amount.to_string())
+            .collect();
+        assert_eq!(sent, vec!["1 sol"]);
+        assert_eq!(history.by_currency(Asset::Sol).count(), 2);
+
+        let failed: Vec<&HistoryEntry> = history.by_status(Status::Failed).collect();
+        assert_eq!(
+            failed[0].error.as_deref(),
+            Some("Insufficient funds for transaction")
+        );
+        assert_eq!(failed[0].fee, None);
+        assert_eq!(failed[1].amount.asset(), Asset::Btc);
+    }
+
+    #[test]
+    fn exports_csv_with_opening_and_closing_balances() {
+        let csv = statement().to_csv();
+        let rows: Vec<Vec<&str>> = csv.lines().map(|line| line.split(',').collect()).collect();
+        assert_eq!(rows.len(), 1 + 3 + 3 + 3);
+        assert_eq!(rows[3], vec!["opening", "", "sol", "3", "", "", "", ""]);
+        assert_eq!(rows[4][2..], ["sol", "1", "0.000005", SOL, "sent", ""]);
+        assert_eq!(
+            rows[9],
+            vec!["closing", "", "sol", "1.999995", "", "", "", ""]
+        );
+        assert!(csv.contains(",\"not,an address\",failed,"));
+    }
+
+    #[test]
+    fn exports_json() {
+        let json = statement().to_json();
+        assert_eq!(json["opening"]["sol"], "3");
+        assert_eq!(json["closing"]["sol"], "1.999995");
+        assert_eq!(json["transactions"][0]["status"], "sent");
+        assert_eq!(json["transactions"][0]["fee"], "0.000005");
+        assert_eq!(json["transactions"][1]["fee"], Value::Null);
+    }
+}
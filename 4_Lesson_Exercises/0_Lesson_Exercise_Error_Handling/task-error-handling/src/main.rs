@@ -1,13 +1,65 @@
 use std::fmt;
+use std::time::Duration;
 
+use anyhow::{Context, bail};
+use ed25519_dalek::{Signer, SigningKey};
+use serde::Serialize;
 use thiserror::Error;
 
-enum ClassicTxError {
+use crate::address::btc::{BtcAddress, BtcAddressError};
+use crate::address::eth::{EthAddress, EthAddressError};
+use crate::address::sol::{SolAddress, SolAddressError};
+use crate::address::{Address, Network};
+use crate::address_book::AddressBook;
+use crate::amount::{Amount, Asset};
+use crate::backend::mock::MockBackend;
+use crate::backend::{BackendError, ChainBackend};
+use crate::context::ContextError;
+use crate::fee::FeeModel;
+use crate::history::{Statement, Status};
+use crate::messages::Locale;
+use crate::multisig::{Decision, Multisig};
+use crate::oracle::table::StaticOracle;
+use crate::oracle::{ConversionLimits, PayWith, Price};
+use crate::policy::{Policy, PolicyRule};
+use crate::retry::RetryPolicy;
+use crate::script::{LineResult, SendTx};
+use crate::store::{StorageError, WalletStore};
+use crate::tx_error::TxError;
+use crate::wallet::Wallet;
+
+mod address;
+mod address_book;
+mod amount;
+mod backend;
+mod batch;
+mod context;
+mod error_code;
+mod fee;
+mod history;
+mod messages;
+mod multisig;
+mod oracle;
+mod policy;
+mod retry;
+mod script;
+mod store;
+mod tx_error;
+mod wallet;
+
+#[derive(Debug, PartialEq)]
+pub enum ClassicTxError {
     InvalidFormat,
     InsufficientFunds,
-    Unauthorized,
+    Unauthorized {
+        rule: PolicyRule,
+        limit: String,
+        attempted: String,
+    },
     SpecificTxError(SpecificTxError),
-    // Task 1 Hint: Add a new variant here
+    InvalidAddress,
+    /// Errors that only exist in `ModernTxError`, kept as they are so no detail is lost.
+    Other(ModernTxError),
 }
 
 impl fmt::Display for ClassicTxError {
@@ -15,10 +67,20 @@ impl fmt::Display for ClassicTxError {
         match self {
             ClassicTxError::InvalidFormat => write!(f, "Transaction has an invalid format"),
             ClassicTxError::InsufficientFunds => write!(f, "Insufficient funds for transaction"),
-            ClassicTxError::Unauthorized => write!(f, "Transaction is unauthorized"),
+            ClassicTxError::Unauthorized {
+                rule,
+                limit,
+                attempted,
+            } => write!(
+                f,
+                "Transaction is unauthorized by the {} rule: limit {}, attempted {}",
+                rule, limit, attempted
+            ),
             ClassicTxError::SpecificTxError(err) => {
                 write!(f, "Specific transaction error: {}", err)
             }
+            ClassicTxError::InvalidAddress => write!(f, "Invalid address provided"),
+            ClassicTxError::Other(err) => write!(f, "{}", err),
         }
     }
 }
@@ -31,21 +93,114 @@ impl From<SpecificTxError> for ClassicTxError {
 
 // This is using the thiserror crate, which is the common way to define errors in modern Rust.
 #[derive(Debug, Error, PartialEq)]
-enum ModernTxError {
+pub enum ModernTxError {
     #[error("Transaction has an invalid format")]
     InvalidFormat,
     #[error("Insufficient funds for transaction")]
     InsufficientFunds,
-    #[error("Transaction is unauthorized")]
-    Unauthorized,
+    #[error("Transaction is unauthorized by the {rule} rule: limit {limit}, attempted {attempted}")]
+    Unauthorized {
+        rule: PolicyRule,
+        limit: String,
+        attempted: String,
+    },
     #[error("Specific transaction error: {0}")]
     SpecificTxError(#[from] SpecificTxError),
-    #[error("Inalid address provided")]
+    #[error("Invalid address provided")]
     InvalidAddress,
+    #[error("Invalid BTC address: {0}")]
+    InvalidBtcAddress(#[from] BtcAddressError),
+    #[error("Invalid ETH address: {0}")]
+    InvalidEthAddress(EthAddressError),
+    #[error("ETH address checksum mismatch for {address}, expected {expected}")]
+    EthChecksumMismatch { address: String, expected: String },
+    #[error("Invalid SOL address: {0}")]
+    InvalidSolAddress(#[from] SolAddressError),
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+    #[error("Unsupported currency: {0}")]
+    UnsupportedCurrency(String),
+    #[error("Arithmetic overflow")]
+    Overflow,
+    #[error(
+        "Insufficient funds for fee: sending {amount} with a fee of {fee}, available {available}"
+    )]
+    InsufficientFundsForFee {
+        amount: Amount,
+        fee: Amount,
+        available: Amount,
+    },
+    #[error("Storage error: {0}")]
+    StorageError(#[from] StorageError),
+    #[error("Duplicate transaction: idempotency key {key} was already used for a different send")]
+    DuplicateTransaction { key: String },
+    #[error("Chain backend error: {0}")]
+    BackendError(#[from] BackendError),
+    #[error("Network mismatch: {address} is a {found} address, but the wallet is on {expected}")]
+    NetworkMismatch {
+        address: String,
+        expected: Network,
+        found: Network,
+    },
+    #[error("Address book entry {label} is for {found}, not {expected}")]
+    LabelCurrencyMismatch {
+        label: String,
+        expected: Asset,
+        found: Asset,
+    },
+    #[error("Sends above {threshold} need multi-signature approval")]
+    ApprovalRequired { threshold: Amount },
+    #[error("No pending send with id {id}")]
+    UnknownPendingSend { id: u64 },
+    #[error("Key {key} is not an approver")]
+    NotAnApprover { key: String },
+    #[error("Invalid approval signature from {key}")]
+    InvalidSignature { key: String },
+    #[error("Key {key} already approved pending send {id}")]
+    AlreadyApproved { id: u64, key: String },
+    #[error("Pending send {id} expired before it was executed")]
+    ApprovalExpired { id: u64 },
+    #[error("Pending send {id} has {approvals} of {required} required approvals")]
+    NotEnoughApprovals {
+        id: u64,
+        required: usize,
+        approvals: usize,
+    },
+    #[error("No price for {base} in {quote}")]
+    UnsupportedPair { base: Asset, quote: Asset },
+    #[error("Price of {base} in {quote} is {age}s old, the limit is {max_age}s")]
+    StalePrice {
+        base: Asset,
+        quote: Asset,
+        age: u64,
+        max_age: u64,
+    },
+    #[error("Slippage exceeded: quoted {quoted}, now {actual}, limit {max_bps} basis points")]
+    SlippageExceeded {
+        quoted: Amount,
+        actual: Amount,
+        max_bps: u32,
+    },
+    #[error("Converting costs {actual}, more than the limit of {max_cost}")]
+    CostLimitExceeded { max_cost: Amount, actual: Amount },
+    #[error("Multisig requires {required} approvals but has only {approvers} approvers")]
+    InvalidMultisig { required: usize, approvers: usize },
 }
 
-#[derive(Debug, Error, PartialEq)]
-struct SpecificTxError {
+// A mistyped checksum gets its own variant, since the address is otherwise well-formed.
+impl From<EthAddressError> for ModernTxError {
+    fn from(err: EthAddressError) -> Self {
+        match err {
+            EthAddressError::ChecksumMismatch { address, expected } => {
+                ModernTxError::EthChecksumMismatch { address, expected }
+            }
+            other => ModernTxError::InvalidEthAddress(other),
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Serialize)]
+pub struct SpecificTxError {
     details: String,
     currency: String,
 }
@@ -74,7 +229,14 @@ fn display_classic_tx_error() -> Result<(), ClassicTxError> {
     println!("Classic Transaction Errors:");
     println!("{}", ClassicTxError::InvalidFormat);
     println!("{}", ClassicTxError::InsufficientFunds);
-    println!("{}", ClassicTxError::Unauthorized);
+    println!(
+        "{}",
+        ClassicTxError::Unauthorized {
+            rule: PolicyRule::MaxTransfer,
+            limit: "1 btc".to_string(),
+            attempted: "2 btc".to_string(),
+        }
+    );
     // function actually returns here because of the ? operator if an error occurs in send_specific_tx
     send_specific_tx("tx details")?;
     Ok(())
@@ -84,7 +246,14 @@ fn display_modern_tx_error() -> Result<(), ModernTxError> {
     println!("Modern Transaction Errors:");
     println!("{}", ModernTxError::InvalidFormat);
     println!("{}", ModernTxError::InsufficientFunds);
-    println!("{}", ModernTxError::Unauthorized);
+    println!(
+        "{}",
+        ModernTxError::Unauthorized {
+            rule: PolicyRule::MaxTransfer,
+            limit: "1 btc".to_string(),
+            attempted: "2 btc".to_string(),
+        }
+    );
     // function actually returns here because of the ? operator if an error occurs in send_specific_tx
     send_specific_tx("tx details")?;
     Ok(())
@@ -94,15 +263,691 @@ fn error_match_showcase(err: &ModernTxError) {
     match err {
         ModernTxError::InvalidFormat => println!("Handle invalid format error"),
         ModernTxError::InsufficientFunds => println!("Handle insufficient funds error"),
-        ModernTxError::Unauthorized => println!("Handle unauthorized error"),
+        ModernTxError::Unauthorized { rule, .. } => {
+            println!("Handle unauthorized error, violated rule: {}", rule)
+        }
         ModernTxError::SpecificTxError(specific_err) => {
             println!("Handle specific transaction error: {}", specific_err)
         }
         ModernTxError::InvalidAddress => println!("Handle invalid address error"),
+        ModernTxError::InvalidBtcAddress(btc_err) => {
+            println!("Handle invalid BTC address error: {}", btc_err)
+        }
+        ModernTxError::InvalidEthAddress(eth_err) => {
+            println!("Handle invalid ETH address error: {}", eth_err)
+        }
+        ModernTxError::EthChecksumMismatch { expected, .. } => {
+            println!("Handle ETH checksum mismatch, expected: {}", expected)
+        }
+        ModernTxError::InvalidSolAddress(sol_err) => {
+            println!("Handle invalid SOL address error: {}", sol_err)
+        }
+        ModernTxError::InvalidAmount(amount) => println!("Handle invalid amount: {}", amount),
+        ModernTxError::UnsupportedCurrency(currency) => {
+            println!("Handle unsupported currency: {}", currency)
+        }
+        ModernTxError::Overflow => println!("Handle overflow error"),
+        ModernTxError::InsufficientFundsForFee { fee, .. } => {
+            println!("Handle insufficient funds for a fee of {}", fee)
+        }
+        ModernTxError::StorageError(storage_err) => {
+            println!("Handle storage error: {}", storage_err)
+        }
+        ModernTxError::DuplicateTransaction { key } => {
+            println!("Handle duplicate transaction, key: {}", key)
+        }
+        ModernTxError::BackendError(backend_err) => {
+            println!("Handle chain backend error: {}", backend_err)
+        }
+        ModernTxError::NetworkMismatch { found, .. } => {
+            println!("Handle network mismatch, address is on {}", found)
+        }
+        ModernTxError::LabelCurrencyMismatch { label, .. } => {
+            println!("Handle address book currency mismatch for {}", label)
+        }
+        ModernTxError::ApprovalRequired { threshold } => {
+            println!("Handle send above the approval threshold {}", threshold)
+        }
+        ModernTxError::UnknownPendingSend { id } => println!("Handle unknown pending send {}", id),
+        ModernTxError::NotAnApprover { key } => {
+            println!("Handle signature from non-approver {}", key)
+        }
+        ModernTxError::InvalidSignature { key } => {
+            println!("Handle invalid signature from {}", key)
+        }
+        ModernTxError::AlreadyApproved { id, .. } => {
+            println!("Handle repeated approval of pending send {}", id)
+        }
+        ModernTxError::ApprovalExpired { id } => println!("Handle expired pending send {}", id),
+        ModernTxError::NotEnoughApprovals { id, .. } => {
+            println!("Handle pending send {} without enough approvals", id)
+        }
+        ModernTxError::UnsupportedPair { base, quote } => {
+            println!("Handle missing price for {}/{}", base, quote)
+        }
+        ModernTxError::StalePrice { base, quote, .. } => {
+            println!("Handle stale price for {}/{}", base, quote)
+        }
+        ModernTxError::SlippageExceeded { quoted, actual, .. } => {
+            println!("Handle price move from {} to {}", quoted, actual)
+        }
+        ModernTxError::CostLimitExceeded { max_cost, .. } => {
+            println!("Handle conversion above the cost limit {}", max_cost)
+        }
+        ModernTxError::InvalidMultisig { required, .. } => {
+            println!("Handle unreachable multisig requirement of {}", required)
+        }
+    }
+}
+
+fn validate_btc_address(address: &str) -> Result<BtcAddress, ModernTxError> {
+    // BtcAddressError is converted into ModernTxError by the ? operator
+    let address = address.parse::<BtcAddress>()?;
+    Ok(address)
+}
+
+fn btc_address_showcase() {
+    println!("BTC Address Validation:");
+    for address in [
+        "1BoatSLRHtKNngkdXEeobR76b53LETtpyT",
+        "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy",
+        "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+        "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+        "1BoatSLRHtKNngkdXEeobR76b53LETtpzz",
+        "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt08O",
+    ] {
+        match validate_btc_address(address) {
+            Ok(btc) => println!("{}: {:?} ({})", btc, btc.address_type(), btc.network()),
+            Err(e) => println!("{}: {}", address, e),
+        }
     }
 }
 
+fn validate_eth_address(address: &str) -> Result<EthAddress, ModernTxError> {
+    let address = address.parse::<EthAddress>()?;
+    Ok(address)
+}
+
+fn eth_address_showcase() {
+    println!("ETH Address Validation:");
+    for address in [
+        "0x742d35Cc6634C0532925a3b844Bc454e4438f44e",
+        "0xde0B295669a9FD93d5F28D9Ec85E40f4cb697BAe",
+        "0x52908400098527886E0F7030069857D2E4169EE7",
+        "0xde0b295669a9fd93d5f28d9ec85e40f4cb697bae",
+        "0xde0B295669a9FD93d5F28D9Ec85E40f4cb697BAE",
+        "0x742d35cc6634c0532925a3b844bc454e4438f44",
+        "742d35Cc6634C0532925a3b844Bc454e4438f44e",
+        "0xZZZZ35Cc6634C0532925a3b844Bc454e4438f44e",
+    ] {
+        match validate_eth_address(address) {
+            Ok(eth) => println!("{}: valid ({})", address, eth.to_checksum_string()),
+            Err(e) => println!("{}: {}", address, e),
+        }
+    }
+}
+
+fn validate_sol_address(address: &str) -> Result<SolAddress, ModernTxError> {
+    let address = address.parse::<SolAddress>()?;
+    Ok(address)
+}
+
+fn sol_address_showcase() {
+    println!("SOL Address Validation:");
+    for address in [
+        "4Nd1mY8iZP2Yvn6tA8FWiVvXqUuY1eWYQfFCJ2yJcQrC",
+        "H3C8dUuWqZK7mtQmVtqzL4qV4byzvNQh6H7v7qSR9zMF",
+        "11111111111111111111111111111111",
+        "GARBAGEADDRESS123456789",
+        "4Nd1mY8iZP2Yvn6tA8FWiVvXqUuY1eWYQfFCJ2yJcQr",
+        "",
+    ] {
+        match validate_sol_address(address) {
+            Ok(sol) => match sol.known_program() {
+                Some(program) => println!("{}: valid, warning: this is the {}", sol, program),
+                None => println!("{}: valid", sol),
+            },
+            Err(e) => println!("{:?}: {}", address, e),
+        }
+    }
+}
+
+fn wallet_showcase() -> Result<(), ModernTxError> {
+    println!("Wallet:");
+    let mut wallet = Wallet::new();
+    wallet.deposit("15 btc".parse()?)?;
+    wallet.deposit("10 eth".parse()?)?;
+    wallet.deposit("5 sol".parse()?)?;
+
+    wallet.withdraw("0.5 btc".parse()?)?;
+    wallet.withdraw("0.000000000000000001 eth".parse()?)?;
+    if let Err(e) = wallet.withdraw("5.000000001 sol".parse()?) {
+        println!("Withdraw failed: {}", e);
+    }
+    if let Err(e) = "0.5 doge".parse::<Amount>() {
+        println!("Parse failed: {}", e);
+    }
+
+    for balance in wallet.balances() {
+        println!("{}", balance);
+    }
+    Ok(())
+}
+
+fn error_json_showcase() -> anyhow::Result<()> {
+    println!("Error JSON:");
+    let errors = [
+        ModernTxError::InsufficientFunds,
+        ModernTxError::from(send_specific_tx("tx details").unwrap_err()),
+        validate_eth_address("0xde0B295669a9FD93d5F28D9Ec85E40f4cb697BAE").unwrap_err(),
+    ];
+    for err in errors {
+        println!(
+            "{} (retryable: {})",
+            serde_json::to_string(&err)?,
+            err.retryable()
+        );
+    }
+    Ok(())
+}
+
+fn batch_showcase() -> Result<(), ModernTxError> {
+    println!("Batch sends:");
+    let mut wallet = Wallet::new();
+    wallet.deposit("1 btc".parse()?)?;
+    let sends = [
+        SendTx {
+            amount: "0.5 btc".parse()?,
+            address: "1BoatSLRHtKNngkdXEeobR76b53LETtpyT".to_string(),
+            pay_with: None,
+            key: None,
+        },
+        SendTx {
+            amount: "0.25 btc".parse()?,
+            address: "1BoatSLRHtKNngkdXEeobR76b53LETtpzz".to_string(),
+            pay_with: None,
+            key: None,
+        },
+        SendTx {
+            amount: "0.75 btc".parse()?,
+            address: "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy".to_string(),
+            pay_with: None,
+            key: None,
+        },
+    ];
+
+    if let Err(e) = batch::send_atomic(&mut wallet, "payroll-1", &sends, &StaticOracle::new()) {
+        println!("{}", e);
+        for (i, err) in &e.failures {
+            println!("  send {}: {}", i, err);
+        }
+    }
+    println!("After atomic batch: {}", wallet.balance(Asset::Btc));
+
+    for (i, result) in
+        batch::send_best_effort(&mut wallet, "payroll-1", &sends, &StaticOracle::new())
+            .iter()
+            .enumerate()
+    {
+        match result {
+            Ok(address) => println!("  send {}: sent to {}", i, address),
+            Err(err) => println!("  send {}: {}", i, err),
+        }
+    }
+    println!("After best effort batch: {}", wallet.balance(Asset::Btc));
+    Ok(())
+}
+
+fn fee_showcase() -> Result<(), ModernTxError> {
+    println!("Fees:");
+    // A congested mempool makes BTC sends more expensive.
+    let mut wallet = Wallet::new().with_fee_model(FeeModel {
+        btc_sat_per_vbyte: 50,
+        ..FeeModel::default()
+    });
+    wallet.deposit("0.01 btc".parse()?)?;
+    for (amount, address) in [
+        ("0.005 btc", "1BoatSLRHtKNngkdXEeobR76b53LETtpyT"),
+        ("0.0049 btc", "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"),
+    ] {
+        match wallet.send(amount.parse()?, address) {
+            Ok(address) => println!("Sent to {}, left: {}", address, wallet.balance(Asset::Btc)),
+            Err(e) => println!("Send failed: {}", e),
+        }
+    }
+    Ok(())
+}
+
+fn policy_showcase() -> Result<(), ModernTxError> {
+    println!("Spending policy:");
+    let sanctioned = Address::parse(Asset::Eth, "0xde0B295669a9FD93d5F28D9Ec85E40f4cb697BAe")?;
+    let treasury = "0x52908400098527886E0F7030069857D2E4169EE7";
+    let policy = Policy::new()
+        .deny(sanctioned)
+        .allow(Address::parse(Asset::Eth, treasury)?)
+        .max_transfer("5 eth".parse()?)
+        .daily_limit("8 eth".parse()?)
+        .required_approvers(2);
+    let mut wallet = Wallet::new().with_policy(policy);
+    wallet.deposit("20 eth".parse()?)?;
+
+    for (amount, address, approvers) in [
+        ("1 eth", "0xde0B295669a9FD93d5F28D9Ec85E40f4cb697BAe", 2),
+        ("1 eth", "0x742d35Cc6634C0532925a3b844Bc454e4438f44e", 2),
+        ("6 eth", treasury, 2),
+        ("4 eth", treasury, 1),
+        ("4 eth", treasury, 2),
+        ("4 eth", treasury, 2),
+        ("4 eth", treasury, 2),
+    ] {
+        match wallet.send_approved(amount.parse()?, address, approvers) {
+            Ok(address) => println!("Sent {} to {}", amount, address),
+            Err(e) => println!("Send failed: {}", e),
+        }
+    }
+    Ok(())
+}
+
+fn store_showcase() -> Result<(), ModernTxError> {
+    println!("Persistent wallet:");
+    let dir = std::env::temp_dir().join(format!("wallet-store-showcase-{}", std::process::id()));
+    let mut store = WalletStore::open(&dir, Wallet::new())?;
+    store.deposit("2 sol".parse()?)?;
+    store.send("0.5 sol".parse()?, "11111111111111111111111111111111")?;
+    store.compact()?;
+    store.update(|wallet| {
+        wallet.send_idempotent(
+            "payout-1",
+            "0.5 sol".parse()?,
+            "11111111111111111111111111111111",
+        )
+    })?;
+    drop(store);
+
+    let store = WalletStore::open(&dir, Wallet::new())?;
+    println!(
+        "Reopened with {}, {} journal record(s) replayed",
+        store.wallet().balance(Asset::Sol),
+        store.recovery().replayed
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+    Ok(())
+}
+
+fn address_book_showcase() -> Result<(), ModernTxError> {
+    println!("Address book:");
+    let mut book = AddressBook::new();
+    book.insert(
+        "alice",
+        Asset::Eth,
+        Network::Mainnet,
+        "0x52908400098527886E0F7030069857D2E4169EE7",
+    )?;
+    book.insert(
+        "faucet",
+        Asset::Btc,
+        Network::Testnet,
+        "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx",
+    )?;
+    let path =
+        std::env::temp_dir().join(format!("address-book-showcase-{}.json", std::process::id()));
+    book.save(&path)?;
+    let book = AddressBook::load(&path)?;
+    let _ = std::fs::remove_file(&path);
+
+    let mut wallet = Wallet::new().with_address_book(book);
+    wallet.deposit("5 eth".parse()?)?;
+    wallet.deposit("1 btc".parse()?)?;
+    for (amount, label) in [
+        ("2 eth", "alice"),
+        ("0.1 btc", "faucet"),
+        ("1 btc", "alice"),
+    ] {
+        match wallet.send(amount.parse()?, label) {
+            Ok(address) => println!("Sent {} to {} ({})", amount, label, address),
+            Err(e) => println!("Send failed: {}", e),
+        }
+    }
+    Ok(())
+}
+
+fn unified_error_showcase() {
+    println!("Unified errors:");
+    let errors: Vec<TxError> = vec![
+        ClassicTxError::InsufficientFunds.into(),
+        ClassicTxError::from(send_specific_tx("tx details").unwrap_err()).into(),
+        validate_btc_address("1BoatSLRHtKNngkdXEeobR76b53LETtpzz")
+            .unwrap_err()
+            .into(),
+    ];
+    for err in errors {
+        println!("{} (code {})", err, err.as_modern().code());
+        let classic = err.into_classic();
+        println!("  as classic: {:?}", classic);
+    }
+}
+
+fn history_showcase() -> Result<(), ModernTxError> {
+    println!("Transaction history:");
+    let mut wallet = Wallet::new();
+    wallet.deposit("3 sol".parse()?)?;
+    wallet.deposit("1 eth".parse()?)?;
+    let opening = wallet.clone();
+    let _ = wallet.send("1 sol".parse()?, "11111111111111111111111111111111");
+    let _ = wallet.send("5 sol".parse()?, "11111111111111111111111111111111");
+    let _ = wallet.send(
+        "0.5 eth".parse()?,
+        "0x52908400098527886E0F7030069857D2E4169EE7",
+    );
+
+    for entry in wallet.history().by_currency(Asset::Sol) {
+        println!("{} to {}: {}", entry.amount, entry.address, entry.status);
+    }
+    for entry in wallet.history().by_status(Status::Failed) {
+        println!("Failed: {}", entry.error.as_deref().unwrap_or_default());
+    }
+    print!("{}", Statement::between(&opening, &wallet).to_csv());
+    Ok(())
+}
+
+fn backend_showcase() -> Result<(), ModernTxError> {
+    println!("Chain backend:");
+    let mut backend = MockBackend::new().with_balance("3 eth".parse()?);
+    let mut wallet = Wallet::new();
+    wallet.deposit("3 eth".parse()?)?;
+    backend.fail_next(BackendError::Timeout);
+    backend.fail_next(BackendError::NonceTooLow {
+        expected: 1,
+        got: 0,
+    });
+    for _ in 0..3 {
+        match wallet.broadcast_send(
+            &mut backend,
+            "1 eth".parse()?,
+            "0x52908400098527886E0F7030069857D2E4169EE7",
+            0,
+        ) {
+            Ok(tx_id) => println!("Broadcast {}", tx_id),
+            Err(e) => println!("Broadcast failed: {} (retryable: {})", e, e.retryable()),
+        }
+    }
+    println!(
+        "{} transfer(s) accepted, on-chain balance: {}",
+        backend.broadcasts().len(),
+        backend.get_balance(Asset::Eth)?
+    );
+    Ok(())
+}
+
+fn retry_showcase() -> Result<(), ModernTxError> {
+    println!("Retries:");
+    let retry = RetryPolicy {
+        initial_delay: Duration::from_millis(1),
+        ..RetryPolicy::default()
+    };
+    let mut backend = MockBackend::new().with_balance("1 eth".parse()?);
+    let mut wallet = Wallet::new();
+    wallet.deposit("1 eth".parse()?)?;
+    let address = "0x52908400098527886E0F7030069857D2E4169EE7";
+
+    // Broadcasts take a fresh nonce on every call, so only failures where the node
+    // certainly did not accept the transfer are safe to retry, not a `Timeout`.
+    backend.fail_next(BackendError::RateLimited);
+    backend.fail_next(BackendError::MempoolFull);
+    match retry.run(|| wallet.broadcast_send(&mut backend, "0.5 eth".parse()?, address, 0)) {
+        Ok(tx_id) => println!("Broadcast {}", tx_id),
+        Err(e) => println!("{}", e),
+    }
+    backend.fail_next(BackendError::MempoolFull);
+    if let Err(e) =
+        retry.run(|| wallet.broadcast_send(&mut backend, "0.6 eth".parse()?, address, 0))
+    {
+        println!("{}", e);
+        println!("Final error code: {}", e.last().code());
+    }
+    Ok(())
+}
+
+fn localized_error_showcase() {
+    println!("User-facing messages:");
+    let err = ModernTxError::InsufficientFundsForFee {
+        amount: Amount::from_base_units(Asset::Eth, 10u128.pow(18)),
+        fee: Amount::from_base_units(Asset::Eth, 420_000_000_000_000),
+        available: Amount::from_base_units(Asset::Eth, 10u128.pow(18)),
+    };
+    println!("developer: {}", err);
+    for tag in ["en", "de-DE", "fr"] {
+        println!("{}: {}", tag, err.user_message(Locale::negotiate(tag)));
+    }
+}
+
+fn multisig_showcase() -> Result<(), ModernTxError> {
+    println!("Multi-signature approvals:");
+    // Simulated treasury keys, derived from fixed seeds.
+    let signers: Vec<SigningKey> = (1..=4).map(|i| SigningKey::from_bytes(&[i; 32])).collect();
+    let approvers = signers[..3].iter().map(|s| s.verifying_key()).collect();
+    if let Err(e) = Multisig::new(
+        "treasury",
+        4,
+        signers[..3].iter().map(|s| s.verifying_key()).collect(),
+    ) {
+        println!("{}", e);
+    }
+    let multisig = Multisig::new("treasury", 2, approvers)?
+        .threshold("10 sol".parse()?)
+        .expires_after(24 * 3600);
+    let mut wallet = Wallet::new().with_multisig(multisig);
+    wallet.deposit("100 sol".parse()?)?;
+    let address = "11111111111111111111111111111111";
+
+    if let Err(e) = wallet.send("50 sol".parse()?, address) {
+        println!("{}", e);
+    }
+    let id = wallet.propose("50 sol".parse()?, address)?;
+    let sign = |wallet: &Wallet, id, signer: &SigningKey, decision| {
+        let pending = wallet.multisig().pending().find(|p| p.id == id);
+        signer.sign(&pending.expect("pending send").message(decision))
+    };
+    for signer in [&signers[0], &signers[3], &signers[0]] {
+        let signature = sign(&wallet, id, signer, Decision::Approve);
+        match wallet.approve(id, &signer.verifying_key(), &signature) {
+            Ok(approvals) => println!("Pending send {} has {} approval(s)", id, approvals),
+            Err(e) => println!("{}", e),
+        }
+        if let Err(e) = wallet.execute(id) {
+            println!("{}", e);
+        }
+    }
+    let signature = sign(&wallet, id, &signers[2], Decision::Approve);
+    wallet.approve(id, &signers[2].verifying_key(), &signature)?;
+    println!("Executed send to {}", wallet.execute(id)?);
+    println!("Balance: {}", wallet.balance(Asset::Sol));
+
+    let id = wallet.propose("20 sol".parse()?, address)?;
+    let signature = sign(&wallet, id, &signers[1], Decision::Reject);
+    let rejected = wallet.reject(id, &signers[1].verifying_key(), &signature)?;
+    println!("Rejected pending send of {}", rejected.amount);
+    println!("Expired: {}", wallet.expire_pending().len());
+    if let Err(e) = wallet.execute(id) {
+        println!("{}", e);
+    }
+    Ok(())
+}
+
+fn conversion_showcase() -> Result<(), ModernTxError> {
+    println!("Cross-asset sends:");
+    let address = "0x52908400098527886E0F7030069857D2E4169EE7";
+    let mut oracle = StaticOracle::new();
+    oracle.insert(Price {
+        base: Asset::Eth,
+        rate: "150 sol".parse()?,
+        timestamp: history::now(),
+    });
+    let mut wallet = Wallet::new().with_conversion_limits(ConversionLimits {
+        max_price_age: 60,
+        max_slippage_bps: 50,
+    });
+    wallet.deposit("1000 sol".parse()?)?;
+
+    let quote = wallet.quote(&oracle, "2 eth".parse()?, address, Asset::Sol)?;
+    println!(
+        "2 eth at {} costs {} plus {} fee",
+        quote.price.rate, quote.cost, quote.cost_fee
+    );
+    oracle.insert(Price {
+        base: Asset::Eth,
+        rate: "152 sol".parse()?,
+        timestamp: history::now(),
+    });
+    if let Err(e) = wallet.send_quoted(&oracle, &quote, None, 0) {
+        println!("{}", e);
+    }
+    for max_cost in [Some("300 sol".parse()?), None] {
+        let pay_with = PayWith {
+            asset: Asset::Sol,
+            max_cost,
+        };
+        match wallet.send_converted(&oracle, &oracle, "2 eth".parse()?, address, pay_with, 0) {
+            Ok(_) => println!("Balance: {}", wallet.balance(Asset::Sol)),
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    oracle.insert(Price {
+        base: Asset::Eth,
+        rate: "152 sol".parse()?,
+        timestamp: 0,
+    });
+    for asset in [Asset::Sol, Asset::Btc] {
+        let pay_with = PayWith {
+            asset,
+            max_cost: None,
+        };
+        if let Err(e) =
+            wallet.send_converted(&oracle, &oracle, "1 eth".parse()?, address, pay_with, 0)
+        {
+            println!("{}", e);
+        }
+    }
+    Ok(())
+}
+
+const RUN_USAGE: &str = "usage: task-error-handling run <script> [--deposit \"<amount> <currency>\"]... \
+                         [--address-book <file>] [--testnet] [--atomic] [--verbose] [--lang <en|de>] [--prices <file>] \
+                         [--statement <file.csv|file.json>]";
+
+// Executes a transaction script against a wallet funded by the --deposit arguments.
+// With --atomic the script runs on a copy of the wallet that is only kept if every line succeeds.
+fn run_command(args: &[String]) -> anyhow::Result<()> {
+    let (path, flags) = args.split_first().context(RUN_USAGE)?;
+    let mut wallet = Wallet::new();
+    let mut atomic = false;
+    let mut verbose = false;
+    let mut statement_path = None;
+    let mut locale = None;
+    let mut oracle = StaticOracle::new();
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "--deposit" => match flags.next() {
+                Some(amount) => wallet.deposit(amount.parse()?)?,
+                None => bail!("--deposit needs an amount\n{}", RUN_USAGE),
+            },
+            "--address-book" => match flags.next() {
+                Some(file) => wallet = wallet.with_address_book(AddressBook::load(file)?),
+                None => bail!("--address-book needs a file\n{}", RUN_USAGE),
+            },
+            "--testnet" => wallet = wallet.with_network(Network::Testnet),
+            "--atomic" => atomic = true,
+            "--verbose" => verbose = true,
+            "--prices" => match flags.next() {
+                Some(file) => oracle = StaticOracle::load(file)?,
+                None => bail!("--prices needs a file\n{}", RUN_USAGE),
+            },
+            "--lang" => match flags.next() {
+                Some(tag) => locale = Some(Locale::negotiate(tag)),
+                None => bail!("--lang needs a language\n{}", RUN_USAGE),
+            },
+            "--statement" => match flags.next() {
+                Some(file) => statement_path = Some(file),
+                None => bail!("--statement needs a file\n{}", RUN_USAGE),
+            },
+            _ => bail!("unexpected argument: {}\n{}", flag, RUN_USAGE),
+        }
+    }
+
+    let contents =
+        std::fs::read_to_string(path).with_context(|| format!("failed to read {}", path))?;
+    let opening = wallet.clone();
+    let mut scratch = wallet.clone();
+    let results = script::run_script(&mut scratch, path, &contents, &oracle);
+    let failed = results.iter().filter(|r| r.result.is_err()).count();
+    let rejected = atomic && failed > 0;
+    let total = results.len();
+    if rejected {
+        let reason = format!("Batch rejected, {} of {} sends failed", failed, total);
+        wallet.keep_history_of(&scratch, &reason);
+    } else {
+        wallet = scratch;
+    }
+
+    for LineResult { line, result } in results {
+        match result {
+            // Nothing was sent, so only the lines that caused the rejection are of interest.
+            Ok(_) if rejected => {}
+            Ok(Address::Sol(sol)) => match sol.known_program() {
+                Some(program) => println!("line {}: OK (warning: target is the {})", line, program),
+                None => println!("line {}: OK", line),
+            },
+            Ok(_) => println!("line {}: OK", line),
+            Err(e) => {
+                match (e.tx_error(), locale) {
+                    (Some(tx_err), Some(locale)) => {
+                        println!("line {}: {}", line, tx_err.user_message(locale))
+                    }
+                    (Some(tx_err), None) => println!("line {}: {}", line, tx_err),
+                    (None, _) => println!("line {}: {}", line, e),
+                }
+                if verbose {
+                    let e = ContextError::new(format!("line {} of {}", line, path), e);
+                    for report_line in e.report().lines().filter(|l| !l.is_empty()) {
+                        println!("    {}", report_line);
+                    }
+                }
+            }
+        }
+    }
+    if rejected {
+        println!(
+            "Batch rejected, {} of {} sends failed, no balances changed",
+            failed, total
+        );
+    }
+
+    println!("Final balances:");
+    for balance in wallet.balances() {
+        println!("{}", balance);
+    }
+
+    if let Some(statement_path) = statement_path {
+        let statement = Statement::between(&opening, &wallet);
+        let contents = if statement_path.ends_with(".json") {
+            serde_json::to_string_pretty(&statement.to_json())? + "\n"
+        } else {
+            statement.to_csv()
+        };
+        std::fs::write(statement_path, contents)
+            .with_context(|| format!("failed to write {}", statement_path))?;
+    }
+    Ok(())
+}
+
 fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("run") {
+        return run_command(&args[2..]);
+    }
+
     if let Err(e) = display_classic_tx_error() {
         println!("Error occurred: {}", e);
     }
@@ -112,6 +957,25 @@ fn main() -> anyhow::Result<()> {
 
     error_match_showcase(&ModernTxError::InsufficientFunds);
 
+    btc_address_showcase();
+    eth_address_showcase();
+    sol_address_showcase();
+
+    wallet_showcase()?;
+    error_json_showcase()?;
+    batch_showcase()?;
+    fee_showcase()?;
+    policy_showcase()?;
+    store_showcase()?;
+    address_book_showcase()?;
+    unified_error_showcase();
+    history_showcase()?;
+    backend_showcase()?;
+    retry_showcase()?;
+    localized_error_showcase();
+    multisig_showcase()?;
+    conversion_showcase()?;
+
     Ok(())
 }
 
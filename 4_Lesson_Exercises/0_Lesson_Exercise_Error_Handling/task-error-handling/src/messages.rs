@@ -0,0 +1,350 @@
+use serde_json::Value;
+
+use crate::ModernTxError;
+
+/// Languages of the end-user messages. `Display` on the errors stays English and is
+/// meant for developers and logs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+}
+
+impl Locale {
+    /// Picks the locale for a language tag such as `de`, `de-AT` or `en_US`, falling back
+    /// to English for languages without a catalogue.
+    pub fn negotiate(tag: &str) -> Locale {
+        let language = tag.split(['-', '_']).next().unwrap_or_default();
+        if language.eq_ignore_ascii_case("de") {
+            Locale::De
+        } else {
+            Locale::En
+        }
+    }
+
+    fn catalogue(self) -> &'static [(u16, &'static str)] {
+        match self {
+            Locale::En => EN,
+            Locale::De => DE,
+        }
+    }
+}
+
+// Templates by error code. `{name}` is replaced with the field of the same name in
+// `ModernTxError::details`. Every code needs an English template, the other languages
+// fall back to it.
+const EN: &[(u16, &str)] = &[
+    (
+        1001,
+        "The transaction could not be read. Please check its format.",
+    ),
+    (1002, "The address is not valid."),
+    (1003, "The Bitcoin address is not valid."),
+    (1004, "The Ethereum address is not valid."),
+    (
+        1005,
+        "The Ethereum address {address} has an invalid checksum. Did you mean {expected}?",
+    ),
+    (1006, "The Solana address is not valid."),
+    (1007, "{amount} is not a valid amount."),
+    (1008, "The currency {currency} is not supported."),
+    (
+        1009,
+        "This transaction was already submitted (reference {key}).",
+    ),
+    (
+        1010,
+        "{address} is a {found} address, but this wallet is on {expected}.",
+    ),
+    (
+        1011,
+        "The address book entry {label} is for {found}, not {expected}.",
+    ),
+    (1012, "There is no pending transaction {id}."),
+    (1013, "{base} cannot be paid with {quote}."),
+    (
+        1014,
+        "{required} approvals cannot be collected from {approvers} approvers.",
+    ),
+    (2001, "Your balance is too low for this transaction."),
+    (2002, "The amount is too large."),
+    (
+        2003,
+        "Your balance of {available} cannot cover {amount} plus a fee of {fee}.",
+    ),
+    (
+        2004,
+        "The price changed from {quoted} to {actual}. Please confirm the new price.",
+    ),
+    (
+        2005,
+        "This transaction would cost {actual}, more than your limit of {max_cost}.",
+    ),
+    (
+        3001,
+        "This transaction is not allowed by the {rule} of your wallet (limit {limit}, attempted {attempted}).",
+    ),
+    (3002, "This key is not allowed to approve transactions."),
+    (3003, "The approval signature is not valid."),
+    (3004, "You already approved pending transaction {id}."),
+    (
+        3005,
+        "Pending transaction {id} has expired. Please submit it again.",
+    ),
+    (
+        3006,
+        "Transactions above {threshold} need to be approved first.",
+    ),
+    (
+        3007,
+        "Pending transaction {id} has {approvals} of {required} required approvals.",
+    ),
+    (4001, "The transaction failed: {details}"),
+    (
+        4002,
+        "The network did not respond in time. Please try again.",
+    ),
+    (
+        4003,
+        "The transaction was sent out of order. Please try again.",
+    ),
+    (4004, "The network rejected the transaction: {reason}"),
+    (4005, "The network is busy. Please try again later."),
+    (4006, "Too many requests. Please try again later."),
+    (
+        4007,
+        "The exchange rate of {base} in {quote} is out of date. Please try again later.",
+    ),
+    (5001, "Your wallet could not be saved. Please try again."),
+];
+
+const DE: &[(u16, &str)] = &[
+    (
+        1001,
+        "Die Transaktion konnte nicht gelesen werden. Bitte prüfen Sie das Format.",
+    ),
+    (1002, "Die Adresse ist ungültig."),
+    (1003, "Die Bitcoin-Adresse ist ungültig."),
+    (1004, "Die Ethereum-Adresse ist ungültig."),
+    (
+        1005,
+        "Die Prüfsumme der Ethereum-Adresse {address} ist ungültig. Meinten Sie {expected}?",
+    ),
+    (1006, "Die Solana-Adresse ist ungültig."),
+    (1007, "{amount} ist kein gültiger Betrag."),
+    (1008, "Die Währung {currency} wird nicht unterstützt."),
+    (
+        1009,
+        "Diese Transaktion wurde bereits übermittelt (Referenz {key}).",
+    ),
+    (
+        1010,
+        "{address} ist eine {found}-Adresse, diese Wallet nutzt {expected}.",
+    ),
+    (
+        1011,
+        "Der Adressbucheintrag {label} ist für {found}, nicht für {expected}.",
+    ),
+    (1012, "Es gibt keine ausstehende Transaktion {id}."),
+    (1013, "{base} kann nicht mit {quote} bezahlt werden."),
+    (
+        1014,
+        "{required} Freigaben können nicht von {approvers} Freigebenden erteilt werden.",
+    ),
+    (2001, "Ihr Guthaben reicht für diese Transaktion nicht aus."),
+    (2002, "Der Betrag ist zu groß."),
+    (
+        2003,
+        "Ihr Guthaben von {available} deckt {amount} zuzüglich einer Gebühr von {fee} nicht.",
+    ),
+    (
+        2004,
+        "Der Preis hat sich von {quoted} auf {actual} geändert. Bitte bestätigen Sie den neuen Preis.",
+    ),
+    (
+        2005,
+        "Diese Transaktion würde {actual} kosten, mehr als Ihr Limit von {max_cost}.",
+    ),
+    (
+        3001,
+        "Diese Transaktion wird durch die Regel „{rule}“ Ihrer Wallet nicht erlaubt (Limit {limit}, versucht {attempted}).",
+    ),
+    (3002, "Dieser Schlüssel darf keine Transaktionen freigeben."),
+    (3003, "Die Signatur der Freigabe ist ungültig."),
+    (
+        3004,
+        "Sie haben die ausstehende Transaktion {id} bereits freigegeben.",
+    ),
+    (
+        3005,
+        "Die ausstehende Transaktion {id} ist abgelaufen. Bitte reichen Sie sie erneut ein.",
+    ),
+    (
+        3006,
+        "Transaktionen über {threshold} müssen zuerst freigegeben werden.",
+    ),
+    (
+        3007,
+        "Die ausstehende Transaktion {id} hat {approvals} von {required} nötigen Freigaben.",
+    ),
+    (4001, "Die Transaktion ist fehlgeschlagen: {details}"),
+    (
+        4002,
+        "Das Netzwerk hat nicht rechtzeitig geantwortet. Bitte versuchen Sie es erneut.",
+    ),
+    (
+        4003,
+        "Die Transaktion wurde in der falschen Reihenfolge gesendet. Bitte versuchen Sie es erneut.",
+    ),
+    (4004, "Das Netzwerk hat die Transaktion abgelehnt: {reason}"),
+    (
+        4005,
+        "Das Netzwerk ist ausgelastet. Bitte versuchen Sie es später erneut.",
+    ),
+    (
+        4006,
+        "Zu viele Anfragen. Bitte versuchen Sie es später erneut.",
+    ),
+    (
+        4007,
+        "Der Wechselkurs von {base} in {quote} ist veraltet. Bitte versuchen Sie es später erneut.",
+    ),
+    (
+        5001,
+        "Ihre Wallet konnte nicht gespeichert werden. Bitte versuchen Sie es erneut.",
+    ),
+];
+
+// Policy rules are identifiers in `details`, users get a name in their language.
+fn rule_name(locale: Locale, rule: &str) -> Option<&'static str> {
+    let name = match (locale, rule) {
+        (Locale::En, "denylist") => "denylist",
+        (Locale::En, "allowlist") => "allowlist",
+        (Locale::En, "max_transfer") => "transfer limit",
+        (Locale::En, "daily_limit") => "daily limit",
+        (Locale::En, "required_approvers") => "approval rule",
+        (Locale::De, "denylist") => "Sperrliste",
+        (Locale::De, "allowlist") => "Positivliste",
+        (Locale::De, "max_transfer") => "Höchstbetrag",
+        (Locale::De, "daily_limit") => "Tageslimit",
+        (Locale::De, "required_approvers") => "Freigabe",
+        _ => return None,
+    };
+    Some(name)
+}
+
+fn template(locale: Locale, code: u16) -> Option<&'static str> {
+    let lookup = |locale: Locale| {
+        locale
+            .catalogue()
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, template)| *template)
+    };
+    lookup(locale).or_else(|| lookup(Locale::En))
+}
+
+impl ModernTxError {
+    /// The message to show an end user in `locale`. Unlike `Display` it does not leak
+    /// internals such as file paths, and it never changes with the developer wording.
+    pub fn user_message(&self, locale: Locale) -> String {
+        let Some(template) = template(locale, self.code()) else {
+            return self.to_string();
+        };
+        let details = self.details();
+        let mut message = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let Some(len) = rest[start..].find('}') else {
+                break;
+            };
+            let name = &rest[start + 1..start + len];
+            message.push_str(&rest[..start]);
+            message.push_str(&field(locale, name, &details[name]));
+            rest = &rest[start + len + 1..];
+        }
+        message.push_str(rest);
+        message
+    }
+}
+
+fn field(locale: Locale, name: &str, value: &Value) -> String {
+    match value {
+        Value::String(s) if name == "rule" => rule_name(locale, s).unwrap_or(s).to_string(),
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::error_code::tests::one_of_each;
+    use crate::policy::PolicyRule;
+
+    // `one_of_each` has every variant, so both catalogues must have exactly its codes.
+    #[test]
+    fn every_code_has_a_complete_english_and_german_message() {
+        let errors = one_of_each();
+        let codes: BTreeSet<u16> = errors.iter().map(ModernTxError::code).collect();
+        for locale in [Locale::En, Locale::De] {
+            let catalogue: BTreeSet<u16> =
+                locale.catalogue().iter().map(|(code, _)| *code).collect();
+            assert_eq!(catalogue, codes, "{:?}", locale);
+            for err in &errors {
+                let message = err.user_message(locale);
+                assert!(!message.contains(['{', '}']), "{}", message);
+            }
+        }
+    }
+
+    #[test]
+    fn interpolates_details() {
+        let err = ModernTxError::InsufficientFundsForFee {
+            amount: "1 sol".parse().unwrap(),
+            fee: "0.000005 sol".parse().unwrap(),
+            available: "1 sol".parse().unwrap(),
+        };
+        assert_eq!(
+            err.user_message(Locale::En),
+            "Your balance of 1 sol cannot cover 1 sol plus a fee of 0.000005 sol."
+        );
+        assert_eq!(
+            err.user_message(Locale::De),
+            "Ihr Guthaben von 1 sol deckt 1 sol zuzüglich einer Gebühr von 0.000005 sol nicht."
+        );
+
+        let err = ModernTxError::Unauthorized {
+            rule: PolicyRule::DailyLimit,
+            limit: "1 btc".to_string(),
+            attempted: "2 btc".to_string(),
+        };
+        assert_eq!(
+            err.user_message(Locale::De),
+            "Diese Transaktion wird durch die Regel „Tageslimit“ Ihrer Wallet nicht erlaubt \
+             (Limit 1 btc, versucht 2 btc)."
+        );
+    }
+
+    #[test]
+    fn user_messages_are_separate_from_display() {
+        let err = ModernTxError::InvalidAddress;
+        assert_eq!(err.to_string(), "Invalid address provided");
+        assert_eq!(err.user_message(Locale::En), "The address is not valid.");
+    }
+
+    #[test]
+    fn unknown_languages_fall_back_to_english() {
+        assert_eq!(Locale::negotiate("de-AT"), Locale::De);
+        assert_eq!(Locale::negotiate("fr"), Locale::En);
+        assert_eq!(
+            template(Locale::De, 1002),
+            Some("Die Adresse ist ungültig.")
+        );
+        assert_eq!(template(Locale::De, 9999), None);
+    }
+}
@@ -0,0 +1,417 @@
+use std::collections::BTreeMap;
+
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+use crate::ModernTxError;
+use crate::address::Address;
+use crate::amount::{Amount, Asset};
+
+/// What an approver signs off on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Approve,
+    Reject,
+}
+
+/// A send above the threshold, waiting for approver signatures. Nothing is debited
+/// until it is executed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingSend {
+    pub id: u64,
+    /// The account of the multisig that queued the send.
+    pub account: String,
+    pub amount: Amount,
+    pub address: Address,
+    /// Seconds since the Unix epoch.
+    pub created: u64,
+    approvals: Vec<VerifyingKey>,
+}
+
+impl PendingSend {
+    /// The bytes an approver signs for `decision`. They cover the whole send, its
+    /// account and creation time, so a signature cannot be reused for another send,
+    /// a send with the same id on another wallet or after a restart, or the opposite
+    /// decision.
+    pub fn message(&self, decision: Decision) -> Vec<u8> {
+        let decision = match decision {
+            Decision::Approve => "approve",
+            Decision::Reject => "reject",
+        };
+        format!(
+            "{} {} {} {} {} {} {}",
+            decision,
+            self.account,
+            self.id,
+            self.created,
+            self.amount.asset(),
+            self.amount.base_units(),
+            self.address
+        )
+        .into_bytes()
+    }
+
+    pub fn approvals(&self) -> usize {
+        self.approvals.len()
+    }
+}
+
+/// Hex form of a public key, as used in error messages.
+pub fn key_id(key: &VerifyingKey) -> String {
+    key.as_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// M-of-N approvals for sends above a per-asset threshold. Any single approver can
+/// reject a pending send. The default requires no approvals for anything.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Multisig {
+    account: String,
+    thresholds: BTreeMap<Asset, u128>,
+    approvers: Vec<VerifyingKey>,
+    required: usize,
+    // Seconds a pending send can wait for its approvals, None waits forever.
+    ttl: Option<u64>,
+    pending: BTreeMap<u64, PendingSend>,
+    next_id: u64,
+}
+
+impl Multisig {
+    /// `required` of the `approvers` have to approve a send above the threshold.
+    /// `account` names the wallet in every signed message and should be unique to it.
+    /// Requiring more approvals than there are approvers is an `InvalidMultisig`.
+    pub fn new(
+        account: &str,
+        required: usize,
+        approvers: Vec<VerifyingKey>,
+    ) -> Result<Self, ModernTxError> {
+        if required > approvers.len() {
+            return Err(ModernTxError::InvalidMultisig {
+                required,
+                approvers: approvers.len(),
+            });
+        }
+        Ok(Multisig {
+            account: account.to_string(),
+            approvers,
+            required,
+            ..Multisig::default()
+        })
+    }
+
+    /// Sends of more than `threshold` need approvals.
+    pub fn threshold(mut self, threshold: Amount) -> Self {
+        self.thresholds
+            .insert(threshold.asset(), threshold.base_units());
+        self
+    }
+
+    pub fn expires_after(mut self, seconds: u64) -> Self {
+        self.ttl = Some(seconds);
+        self
+    }
+
+    /// The threshold `amount` exceeds, if any.
+    pub fn exceeded_threshold(&self, amount: Amount) -> Option<Amount> {
+        let asset = amount.asset();
+        self.thresholds
+            .get(&asset)
+            .filter(|&&threshold| amount.base_units() > threshold)
+            .map(|&threshold| Amount::from_base_units(asset, threshold))
+    }
+
+    pub fn pending(&self) -> impl Iterator<Item = &PendingSend> {
+        self.pending.values()
+    }
+
+    /// Queues a send for approval and returns its id.
+    pub fn propose(&mut self, amount: Amount, address: Address, now: u64) -> u64 {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.pending.insert(
+            id,
+            PendingSend {
+                id,
+                account: self.account.clone(),
+                amount,
+                address,
+                created: now,
+                approvals: Vec::new(),
+            },
+        );
+        id
+    }
+
+    /// Adds the approval of `key`, returning how many approvals the send has now.
+    pub fn approve(
+        &mut self,
+        id: u64,
+        key: &VerifyingKey,
+        signature: &Signature,
+        now: u64,
+    ) -> Result<usize, ModernTxError> {
+        let pending = self.verify(id, key, signature, Decision::Approve, now)?;
+        if pending.approvals.contains(key) {
+            return Err(ModernTxError::AlreadyApproved {
+                id,
+                key: key_id(key),
+            });
+        }
+        let pending = self.pending.get_mut(&id).expect("verified above");
+        pending.approvals.push(*key);
+        Ok(pending.approvals.len())
+    }
+
+    /// Drops the pending send, even if it already has enough approvals.
+    pub fn reject(
+        &mut self,
+        id: u64,
+        key: &VerifyingKey,
+        signature: &Signature,
+        now: u64,
+    ) -> Result<PendingSend, ModernTxError> {
+        self.verify(id, key, signature, Decision::Reject, now)?;
+        Ok(self.pending.remove(&id).expect("verified above"))
+    }
+
+    /// Removes and returns every pending send that ran out of time at `now`.
+    pub fn expire(&mut self, now: u64) -> Vec<PendingSend> {
+        let expired: Vec<u64> = self
+            .pending
+            .values()
+            .filter(|pending| self.expired(pending, now))
+            .map(|pending| pending.id)
+            .collect();
+        expired
+            .into_iter()
+            .filter_map(|id| self.pending.remove(&id))
+            .collect()
+    }
+
+    /// The pending send, if it collected enough approvals and has not expired. It stays
+    /// pending until `complete` is called, so a failed execution can be retried.
+    pub fn approved(&self, id: u64, now: u64) -> Result<&PendingSend, ModernTxError> {
+        let pending = self.get(id, now)?;
+        if pending.approvals.len() < self.required {
+            return Err(ModernTxError::NotEnoughApprovals {
+                id,
+                required: self.required,
+                approvals: pending.approvals.len(),
+            });
+        }
+        Ok(pending)
+    }
+
+    pub fn complete(&mut self, id: u64) -> Option<PendingSend> {
+        self.pending.remove(&id)
+    }
+
+    fn get(&self, id: u64, now: u64) -> Result<&PendingSend, ModernTxError> {
+        let pending = self
+            .pending
+            .get(&id)
+            .ok_or(ModernTxError::UnknownPendingSend { id })?;
+        if self.expired(pending, now) {
+            return Err(ModernTxError::ApprovalExpired { id });
+        }
+        Ok(pending)
+    }
+
+    fn verify(
+        &self,
+        id: u64,
+        key: &VerifyingKey,
+        signature: &Signature,
+        decision: Decision,
+        now: u64,
+    ) -> Result<&PendingSend, ModernTxError> {
+        if !self.approvers.contains(key) {
+            return Err(ModernTxError::NotAnApprover { key: key_id(key) });
+        }
+        let pending = self.get(id, now)?;
+        key.verify(&pending.message(decision), signature)
+            .map_err(|_| ModernTxError::InvalidSignature { key: key_id(key) })?;
+        Ok(pending)
+    }
+
+    fn expired(&self, pending: &PendingSend, now: u64) -> bool {
+        self.ttl
+            .is_some_and(|ttl| now >= pending.created.saturating_add(ttl))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{Signer, SigningKey};
+
+    use super::*;
+
+    const SOL: &str = "11111111111111111111111111111111";
+
+    fn signers() -> Vec<SigningKey> {
+        (1..=4).map(|i| SigningKey::from_bytes(&[i; 32])).collect()
+    }
+
+    // Two of the first three signers, the fourth is an outsider.
+    fn multisig() -> Multisig {
+        let approvers = signers()[..3].iter().map(|s| s.verifying_key()).collect();
+        Multisig::new("treasury", 2, approvers)
+            .unwrap()
+            .threshold("10 sol".parse().unwrap())
+            .expires_after(3600)
+    }
+
+    fn propose(multisig: &mut Multisig) -> u64 {
+        propose_at(multisig, 1000)
+    }
+
+    fn propose_at(multisig: &mut Multisig, now: u64) -> u64 {
+        let address = Address::parse(Asset::Sol, SOL).unwrap();
+        multisig.propose("50 sol".parse().unwrap(), address, now)
+    }
+
+    #[test]
+    fn rejects_more_required_approvals_than_approvers() {
+        let approvers: Vec<VerifyingKey> =
+            signers()[..2].iter().map(|s| s.verifying_key()).collect();
+        assert_eq!(
+            Multisig::new("treasury", 3, approvers.clone()),
+            Err(ModernTxError::InvalidMultisig {
+                required: 3,
+                approvers: 2
+            })
+        );
+        assert!(Multisig::new("treasury", 2, approvers).is_ok());
+    }
+
+    fn sign(multisig: &Multisig, id: u64, signer: &SigningKey, decision: Decision) -> Signature {
+        let pending = multisig.pending().find(|p| p.id == id).unwrap();
+        signer.sign(&pending.message(decision))
+    }
+
+    fn approve(
+        multisig: &mut Multisig,
+        id: u64,
+        signer: &SigningKey,
+    ) -> Result<usize, ModernTxError> {
+        let signature = sign(multisig, id, signer, Decision::Approve);
+        multisig.approve(id, &signer.verifying_key(), &signature, 1000)
+    }
+
+    #[test]
+    fn applies_thresholds_per_asset() {
+        let multisig = multisig();
+        assert_eq!(multisig.exceeded_threshold("10 sol".parse().unwrap()), None);
+        assert_eq!(
+            multisig.exceeded_threshold("10.5 sol".parse().unwrap()),
+            Some("10 sol".parse().unwrap())
+        );
+        assert_eq!(
+            multisig.exceeded_threshold("100 btc".parse().unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn needs_m_of_n_distinct_approvals() {
+        let signers = signers();
+        let mut multisig = multisig();
+        let id = propose(&mut multisig);
+
+        assert_eq!(approve(&mut multisig, id, &signers[0]), Ok(1));
+        assert_eq!(
+            multisig.approved(id, 1000).unwrap_err(),
+            ModernTxError::NotEnoughApprovals {
+                id,
+                required: 2,
+                approvals: 1
+            }
+        );
+        assert_eq!(
+            approve(&mut multisig, id, &signers[0]),
+            Err(ModernTxError::AlreadyApproved {
+                id,
+                key: key_id(&signers[0].verifying_key())
+            })
+        );
+        assert_eq!(
+            approve(&mut multisig, id, &signers[3]),
+            Err(ModernTxError::NotAnApprover {
+                key: key_id(&signers[3].verifying_key())
+            })
+        );
+        assert_eq!(approve(&mut multisig, id, &signers[2]), Ok(2));
+
+        assert_eq!(multisig.approved(id, 1000).unwrap().approvals(), 2);
+        multisig.complete(id).unwrap();
+        assert_eq!(
+            multisig.approved(id, 1000).unwrap_err(),
+            ModernTxError::UnknownPendingSend { id }
+        );
+    }
+
+    #[test]
+    fn signatures_must_cover_the_send_and_decision() {
+        let signers = signers();
+        let mut multisig = multisig();
+        let id = propose(&mut multisig);
+        let other = propose(&mut multisig);
+        let key = signers[1].verifying_key();
+
+        let invalid = Err(ModernTxError::InvalidSignature { key: key_id(&key) });
+        let for_other_send = sign(&multisig, other, &signers[1], Decision::Approve);
+        assert_eq!(multisig.approve(id, &key, &for_other_send, 1000), invalid);
+        let rejection = sign(&multisig, id, &signers[1], Decision::Reject);
+        assert_eq!(multisig.approve(id, &key, &rejection, 1000), invalid);
+
+        assert_eq!(multisig.reject(id, &key, &rejection, 1000).unwrap().id, id);
+        assert_eq!(multisig.pending().count(), 1);
+    }
+
+    #[test]
+    fn signatures_are_bound_to_the_account_and_creation_time() {
+        let signers = signers();
+        let key = signers[1].verifying_key();
+        let invalid = Err(ModernTxError::InvalidSignature { key: key_id(&key) });
+
+        // The same first send on another wallet.
+        let mut treasury = multisig();
+        let id = propose(&mut treasury);
+        let approvers = signers[..3].iter().map(|s| s.verifying_key()).collect();
+        let mut payroll = Multisig::new("payroll", 2, approvers).unwrap();
+        assert_eq!(propose(&mut payroll), id);
+        let for_payroll = sign(&payroll, id, &signers[1], Decision::Approve);
+        assert_eq!(treasury.approve(id, &key, &for_payroll, 1000), invalid);
+
+        // The same first send after a restart.
+        let mut restarted = multisig();
+        assert_eq!(propose_at(&mut restarted, 2000), id);
+        let before_restart = sign(&treasury, id, &signers[1], Decision::Approve);
+        assert_eq!(restarted.approve(id, &key, &before_restart, 2000), invalid);
+    }
+
+    #[test]
+    fn pending_sends_expire() {
+        let signers = signers();
+        let mut multisig = multisig();
+        let id = propose(&mut multisig);
+        approve(&mut multisig, id, &signers[0]).unwrap();
+        approve(&mut multisig, id, &signers[1]).unwrap();
+
+        assert!(multisig.approved(id, 4599).is_ok());
+        assert_eq!(
+            multisig.approved(id, 4600).unwrap_err(),
+            ModernTxError::ApprovalExpired { id }
+        );
+        let signature = sign(&multisig, id, &signers[2], Decision::Approve);
+        assert_eq!(
+            multisig.approve(id, &signers[2].verifying_key(), &signature, 4600),
+            Err(ModernTxError::ApprovalExpired { id })
+        );
+
+        let expired: Vec<u64> = multisig.expire(4600).iter().map(|p| p.id).collect();
+        assert_eq!(expired, vec![id]);
+        assert_eq!(multisig.pending().count(), 0);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::ModernTxError;
+use crate::address::Address;
+use crate::amount::{Amount, Asset};
+
+pub mod table;
+
+/// What one whole coin of `base` is worth in another asset at `timestamp`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Price {
+    pub base: Asset,
+    /// The value of one `base` coin, in the quote asset.
+    pub rate: Amount,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+impl Price {
+    pub fn quote(&self) -> Asset {
+        self.rate.asset()
+    }
+
+    /// The price of one quote coin in `base`, rounded up to a base unit like `convert`.
+    pub fn inverse(&self) -> Result<Price, ModernTxError> {
+        let scale = 10u128.pow(self.base.decimals() + self.quote().decimals());
+        if self.rate.base_units() == 0 {
+            return Err(ModernTxError::Overflow);
+        }
+        let rate = scale.div_ceil(self.rate.base_units());
+        Ok(Price {
+            base: self.quote(),
+            rate: Amount::from_base_units(self.base, rate),
+            timestamp: self.timestamp,
+        })
+    }
+
+    /// Converts an amount of `base` into the quote asset, rounding up so the payer never
+    /// pays less than the price.
+    pub fn convert(&self, amount: Amount) -> Result<Amount, ModernTxError> {
+        debug_assert_eq!(amount.asset(), self.base);
+        let scale = 10u128.pow(self.base.decimals());
+        let base_units = amount
+            .base_units()
+            .checked_mul(self.rate.base_units())
+            .ok_or(ModernTxError::Overflow)?
+            .div_ceil(scale);
+        Ok(Amount::from_base_units(self.quote(), base_units))
+    }
+}
+
+/// A source of exchange rates.
+pub trait PriceOracle {
+    /// The price of `base` in `quote`, or `UnsupportedPair` if the oracle has none.
+    fn price(&self, base: Asset, quote: Asset) -> Result<Price, ModernTxError>;
+}
+
+/// The asset a send is paid in when it is not its own, and the most it may cost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayWith {
+    pub asset: Asset,
+    /// A cap on the total cost, fee included, in `asset`.
+    pub max_cost: Option<Amount>,
+}
+
+/// How old a price may be and how far it may move between quoting and sending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConversionLimits {
+    /// Seconds.
+    pub max_price_age: u64,
+    /// Basis points the cost may rise over the quote, 100 is 1%.
+    pub max_slippage_bps: u32,
+}
+
+impl Default for ConversionLimits {
+    fn default() -> Self {
+        ConversionLimits {
+            max_price_age: 300,
+            max_slippage_bps: 100,
+        }
+    }
+}
+
+/// The cost of sending `amount` to `address` when paying in another asset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quote {
+    pub amount: Amount,
+    pub address: Address,
+    /// The network fee, in the asset of `amount`.
+    pub fee: Amount,
+    pub price: Price,
+    /// `amount` and `fee` converted into the asset paid with.
+    pub cost: Amount,
+    pub cost_fee: Amount,
+}
+
+impl Quote {
+    pub fn total(&self) -> Result<Amount, ModernTxError> {
+        let total = self
+            .cost
+            .base_units()
+            .checked_add(self.cost_fee.base_units())
+            .ok_or(ModernTxError::Overflow)?;
+        Ok(Amount::from_base_units(self.cost.asset(), total))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eth_in_sol() -> Price {
+        Price {
+            base: Asset::Eth,
+            rate: "150.25 sol".parse().unwrap(),
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn converts_rounding_up() {
+        let price = eth_in_sol();
+        assert_eq!(
+            price.convert("2 eth".parse().unwrap()).unwrap().to_string(),
+            "300.5 sol"
+        );
+        // 1 wei is worth a fraction of a lamport, which still costs a whole one.
+        assert_eq!(
+            price
+                .convert("0.000000000000000001 eth".parse().unwrap())
+                .unwrap(),
+            Amount::from_base_units(Asset::Sol, 1)
+        );
+    }
+
+    #[test]
+    fn inverts() {
+        let inverse = eth_in_sol().inverse().unwrap();
+        assert_eq!(inverse.base, Asset::Sol);
+        assert_eq!(inverse.rate.to_string(), "0.006655574043261232 eth");
+        // Both roundings favor the price, so converting back never undercuts it.
+        let eth = inverse.convert("150.25 sol".parse().unwrap()).unwrap();
+        assert_eq!(eth.to_string(), "1.000000000000000108 eth");
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use super::{Price, PriceOracle};
+use crate::ModernTxError;
+use crate::amount::{Amount, Asset};
+use crate::history;
+use crate::store::StorageError;
+
+/// Fixed prices, e.g. loaded from a file. A price of `base` in `quote` also answers
+/// for `quote` in `base`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StaticOracle {
+    prices: BTreeMap<(Asset, Asset), Price>,
+}
+
+impl StaticOracle {
+    pub fn new() -> Self {
+        StaticOracle::default()
+    }
+
+    /// Adds or replaces the price of its pair.
+    pub fn insert(&mut self, price: Price) {
+        self.prices.insert((price.base, price.quote()), price);
+    }
+
+    /// Reads lines of `<base> <quote> <price> [<timestamp>]`, e.g. `eth sol 150.25`,
+    /// with `#` comments. Prices without a timestamp are as old as the load.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ModernTxError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|source| StorageError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let loaded = history::now();
+
+        let mut oracle = StaticOracle::new();
+        for (i, line) in contents.lines().enumerate() {
+            let code = line.split_once('#').map_or(line, |(code, _)| code);
+            let fields: Vec<&str> = code.split_whitespace().collect();
+            if fields.is_empty() {
+                continue;
+            }
+            let price =
+                parse_price(&fields, loaded).map_err(|reason| StorageError::CorruptRecord {
+                    path: path.to_path_buf(),
+                    line: i + 1,
+                    reason,
+                })?;
+            oracle.insert(price);
+        }
+        Ok(oracle)
+    }
+}
+
+fn parse_price(fields: &[&str], loaded: u64) -> Result<Price, String> {
+    let (base, quote, rate, timestamp) = match fields {
+        [base, quote, rate] => (base, quote, rate, None),
+        [base, quote, rate, timestamp] => (base, quote, rate, Some(timestamp)),
+        _ => return Err("expected <base> <quote> <price> [<timestamp>]".to_string()),
+    };
+    let base: Asset = base.parse().map_err(|e: ModernTxError| e.to_string())?;
+    let quote: Asset = quote.parse().map_err(|e: ModernTxError| e.to_string())?;
+    let rate = Amount::parse_decimal(quote, rate).map_err(|e| e.to_string())?;
+    if base == quote || rate.base_units() == 0 {
+        return Err(format!("not a price: {} {}", base, rate));
+    }
+    let timestamp = match timestamp {
+        Some(timestamp) => timestamp
+            .parse()
+            .map_err(|_| format!("invalid timestamp: {}", timestamp))?,
+        None => loaded,
+    };
+    Ok(Price {
+        base,
+        rate,
+        timestamp,
+    })
+}
+
+impl PriceOracle for StaticOracle {
+    fn price(&self, base: Asset, quote: Asset) -> Result<Price, ModernTxError> {
+        if base == quote {
+            return Ok(Price {
+                base,
+                rate: Amount::from_base_units(quote, 10u128.pow(quote.decimals())),
+                timestamp: history::now(),
+            });
+        }
+        if let Some(price) = self.prices.get(&(base, quote)) {
+            return Ok(*price);
+        }
+        match self.prices.get(&(quote, base)) {
+            Some(price) => price.inverse(),
+            None => Err(ModernTxError::UnsupportedPair { base, quote }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_prices_and_answers_both_directions() {
+        let path = std::env::temp_dir().join(format!("prices-{}.txt", std::process::id()));
+        fs::write(
+            &path,
+            "# base quote price\neth sol 150.25 1700000000\nbtc eth 20\n",
+        )
+        .unwrap();
+        let oracle = StaticOracle::load(&path).unwrap();
+
+        let price = oracle.price(Asset::Eth, Asset::Sol).unwrap();
+        assert_eq!(price.rate.to_string(), "150.25 sol");
+        assert_eq!(price.timestamp, 1_700_000_000);
+        let price = oracle.price(Asset::Eth, Asset::Btc).unwrap();
+        assert_eq!(price.rate.to_string(), "0.05 btc");
+        assert!(price.timestamp >= history::now() - 60);
+        assert_eq!(
+            oracle.price(Asset::Btc, Asset::Sol),
+            Err(ModernTxError::UnsupportedPair {
+                base: Asset::Btc,
+                quote: Asset::Sol
+            })
+        );
+
+        fs::write(&path, "eth sol 150.25\nsol eth\n").unwrap();
+        assert_eq!(
+            StaticOracle::load(&path),
+            Err(ModernTxError::StorageError(StorageError::CorruptRecord {
+                path: path.clone(),
+                line: 2,
+                reason: "expected <base> <quote> <price> [<timestamp>]".to_string(),
+            }))
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,269 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::ModernTxError;
+use crate::address::Address;
+use crate::amount::{Amount, Asset};
+
+/// The policy rule a send violated, as recorded in `ModernTxError::Unauthorized`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyRule {
+    Denylist,
+    Allowlist,
+    MaxTransfer,
+    DailyLimit,
+    RequiredApprovers,
+}
+
+impl fmt::Display for PolicyRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyRule::Denylist => write!(f, "denylist"),
+            PolicyRule::Allowlist => write!(f, "allowlist"),
+            PolicyRule::MaxTransfer => write!(f, "max_transfer"),
+            PolicyRule::DailyLimit => write!(f, "daily_limit"),
+            PolicyRule::RequiredApprovers => write!(f, "required_approvers"),
+        }
+    }
+}
+
+/// Days since the Unix epoch in UTC, the unit daily limits are tracked in.
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() / 86_400)
+}
+
+/// Rules every send has to pass before funds are touched, plus the amounts already
+/// sent per asset on the current day. The default policy allows everything.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Policy {
+    daily_limits: BTreeMap<Asset, u128>,
+    max_transfers: BTreeMap<Asset, u128>,
+    // None allows any address that is not denylisted.
+    allowlist: Option<Vec<Address>>,
+    denylist: Vec<Address>,
+    required_approvers: usize,
+    spent: BTreeMap<Asset, (u64, u128)>,
+}
+
+impl Policy {
+    pub fn new() -> Self {
+        Policy::default()
+    }
+
+    pub fn daily_limit(mut self, limit: Amount) -> Self {
+        self.daily_limits.insert(limit.asset(), limit.base_units());
+        self
+    }
+
+    pub fn max_transfer(mut self, limit: Amount) -> Self {
+        self.max_transfers.insert(limit.asset(), limit.base_units());
+        self
+    }
+
+    /// Once any address is allowed, sends to addresses that are not are rejected.
+    pub fn allow(mut self, address: Address) -> Self {
+        self.allowlist.get_or_insert_with(Vec::new).push(address);
+        self
+    }
+
+    pub fn deny(mut self, address: Address) -> Self {
+        self.denylist.push(address);
+        self
+    }
+
+    pub fn required_approvers(mut self, count: usize) -> Self {
+        self.required_approvers = count;
+        self
+    }
+
+    /// Checks a send of `amount` to `address`, signed off by `approvers` people, on
+    /// `day`. The first violated rule is reported.
+    pub fn check(
+        &self,
+        amount: Amount,
+        address: &Address,
+        approvers: usize,
+        day: u64,
+    ) -> Result<(), ModernTxError> {
+        let asset = amount.asset();
+        if self.denylist.contains(address) {
+            return Err(unauthorized(
+                PolicyRule::Denylist,
+                "addresses not on the denylist",
+                address,
+            ));
+        }
+        if let Some(allowlist) = &self.allowlist
+            && !allowlist.contains(address)
+        {
+            return Err(unauthorized(
+                PolicyRule::Allowlist,
+                "addresses on the allowlist",
+                address,
+            ));
+        }
+        if let Some(&limit) = self.max_transfers.get(&asset)
+            && amount.base_units() > limit
+        {
+            return Err(unauthorized(
+                PolicyRule::MaxTransfer,
+                Amount::from_base_units(asset, limit),
+                amount,
+            ));
+        }
+        if let Some(&limit) = self.daily_limits.get(&asset) {
+            let total = self
+                .spent_on(asset, day)
+                .saturating_add(amount.base_units());
+            if total > limit {
+                return Err(unauthorized(
+                    PolicyRule::DailyLimit,
+                    Amount::from_base_units(asset, limit),
+                    Amount::from_base_units(asset, total),
+                ));
+            }
+        }
+        if approvers < self.required_approvers {
+            return Err(unauthorized(
+                PolicyRule::RequiredApprovers,
+                self.required_approvers,
+                approvers,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Counts a completed send towards the daily limit of `day`.
+    pub fn record(&mut self, amount: Amount, day: u64) {
+        let spent = self.spent_on(amount.asset(), day);
+        self.spent.insert(
+            amount.asset(),
+            (day, spent.saturating_add(amount.base_units())),
+        );
+    }
+
+    fn spent_on(&self, asset: Asset, day: u64) -> u128 {
+        match self.spent.get(&asset) {
+            Some(&(spent_day, spent)) if spent_day == day => spent,
+            _ => 0,
+        }
+    }
+}
+
+fn unauthorized(rule: PolicyRule, limit: impl ToString, attempted: impl ToString) -> ModernTxError {
+    ModernTxError::Unauthorized {
+        rule,
+        limit: limit.to_string(),
+        attempted: attempted.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BTC: &str = "1BoatSLRHtKNngkdXEeobR76b53LETtpyT";
+    const OTHER_BTC: &str = "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy";
+
+    fn address(s: &str) -> Address {
+        Address::parse(Asset::Btc, s).unwrap()
+    }
+
+    fn amount(s: &str) -> Amount {
+        s.parse().unwrap()
+    }
+
+    fn violated(result: Result<(), ModernTxError>) -> (PolicyRule, String, String) {
+        match result {
+            Err(ModernTxError::Unauthorized {
+                rule,
+                limit,
+                attempted,
+            }) => (rule, limit, attempted),
+            other => panic!("expected a policy violation, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn default_policy_allows_everything() {
+        let policy = Policy::new();
+        assert_eq!(
+            policy.check(amount("1000 btc"), &address(BTC), 0, 0),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn address_lists() {
+        let policy = Policy::new().allow(address(BTC));
+        assert_eq!(policy.check(amount("1 btc"), &address(BTC), 0, 0), Ok(()));
+        assert_eq!(
+            violated(policy.check(amount("1 btc"), &address(OTHER_BTC), 0, 0)),
+            (
+                PolicyRule::Allowlist,
+                "addresses on the allowlist".to_string(),
+                OTHER_BTC.to_string()
+            )
+        );
+
+        // The denylist wins over the allowlist.
+        let policy = policy.deny(address(BTC));
+        assert_eq!(
+            violated(policy.check(amount("1 btc"), &address(BTC), 0, 0)).0,
+            PolicyRule::Denylist
+        );
+    }
+
+    #[test]
+    fn max_transfer_is_per_asset() {
+        let policy = Policy::new().max_transfer(amount("1 btc"));
+        assert_eq!(policy.check(amount("1 btc"), &address(BTC), 0, 0), Ok(()));
+        assert_eq!(
+            violated(policy.check(amount("1.5 btc"), &address(BTC), 0, 0)),
+            (
+                PolicyRule::MaxTransfer,
+                "1 btc".to_string(),
+                "1.5 btc".to_string()
+            )
+        );
+
+        let sol = Address::parse(Asset::Sol, "11111111111111111111111111111111").unwrap();
+        assert_eq!(policy.check(amount("1000 sol"), &sol, 0, 0), Ok(()));
+    }
+
+    #[test]
+    fn daily_limit_counts_recorded_sends_and_resets_the_next_day() {
+        let mut policy = Policy::new().daily_limit(amount("5 btc"));
+        policy.record(amount("3 btc"), 100);
+        assert_eq!(policy.check(amount("2 btc"), &address(BTC), 0, 100), Ok(()));
+        assert_eq!(
+            violated(policy.check(amount("2.5 btc"), &address(BTC), 0, 100)),
+            (
+                PolicyRule::DailyLimit,
+                "5 btc".to_string(),
+                "5.5 btc".to_string()
+            )
+        );
+        assert_eq!(policy.check(amount("5 btc"), &address(BTC), 0, 101), Ok(()));
+    }
+
+    #[test]
+    fn required_approvers() {
+        let policy = Policy::new().required_approvers(2);
+        assert_eq!(
+            violated(policy.check(amount("1 btc"), &address(BTC), 1, 0)),
+            (
+                PolicyRule::RequiredApprovers,
+                "2".to_string(),
+                "1".to_string()
+            )
+        );
+        assert_eq!(policy.check(amount("1 btc"), &address(BTC), 2, 0), Ok(()));
+    }
+}
@@ -0,0 +1,271 @@
+use std::fmt;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use thiserror::Error;
+
+use crate::ModernTxError;
+
+/// Why the executor stopped retrying.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GiveUp {
+    /// The last error will not go away by retrying, see `ModernTxError::retryable`.
+    Permanent,
+    AttemptsExhausted,
+    /// Waiting for the next attempt would exceed the delay budget.
+    BudgetExhausted,
+}
+
+impl fmt::Display for GiveUp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GiveUp::Permanent => write!(f, "permanent error"),
+            GiveUp::AttemptsExhausted => write!(f, "attempts exhausted"),
+            GiveUp::BudgetExhausted => write!(f, "budget exhausted"),
+        }
+    }
+}
+
+/// One failed attempt and how long the executor waited after it.
+#[derive(Debug, PartialEq)]
+pub struct Attempt {
+    pub error: ModernTxError,
+    pub delay: Option<Duration>,
+}
+
+/// Every failed attempt of an operation that never succeeded, oldest first.
+#[derive(Debug, Error, PartialEq)]
+#[error("Gave up after {} attempt(s) ({reason}): {}", attempts.len(), list(attempts))]
+pub struct RetryError {
+    pub reason: GiveUp,
+    pub attempts: Vec<Attempt>,
+}
+
+impl RetryError {
+    pub fn last(&self) -> &ModernTxError {
+        // The executor only gives up after at least one failed attempt.
+        &self.attempts.last().expect("at least one attempt").error
+    }
+}
+
+fn list(attempts: &[Attempt]) -> String {
+    let attempts: Vec<String> = attempts
+        .iter()
+        .enumerate()
+        .map(|(i, attempt)| format!("{}: {}", i + 1, attempt.error))
+        .collect();
+    attempts.join("; ")
+}
+
+/// Exponential backoff with jitter. The n-th retry waits a random duration between
+/// half and all of `initial_delay * 2^(n-1)`, capped at `max_delay`, so clients that
+/// failed together do not retry together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    /// The most time spent waiting between attempts, in total.
+    pub budget: Duration,
+    pub seed: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.subsec_nanos());
+        RetryPolicy {
+            max_attempts: 5,
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            budget: Duration::from_secs(10),
+            seed: u64::from(nanos),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Runs `op` until it succeeds, fails permanently or the policy gives up. A `Timeout`
+    /// does not tell whether `op` took effect, so `op` has to be idempotent to be retried
+    /// safely. `Wallet::broadcast_send` is not: every call takes a fresh nonce and may
+    /// send a second time.
+    pub fn run<T>(&self, op: impl FnMut() -> Result<T, ModernTxError>) -> Result<T, RetryError> {
+        self.run_with_sleep(thread::sleep, op)
+    }
+
+    /// Like `run`, but waits with `sleep`, so tests do not have to.
+    pub fn run_with_sleep<T>(
+        &self,
+        mut sleep: impl FnMut(Duration),
+        mut op: impl FnMut() -> Result<T, ModernTxError>,
+    ) -> Result<T, RetryError> {
+        let mut rng = self.seed | 1;
+        let mut waited = Duration::ZERO;
+        let mut attempts: Vec<Attempt> = Vec::new();
+        loop {
+            let error = match op() {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+
+            let reason = if !error.retryable() {
+                Some(GiveUp::Permanent)
+            } else if attempts.len() + 1 >= self.max_attempts as usize {
+                Some(GiveUp::AttemptsExhausted)
+            } else {
+                None
+            };
+            let delay = self.delay(attempts.len() as u32, &mut rng);
+            let reason = reason
+                .or_else(|| (waited + delay > self.budget).then_some(GiveUp::BudgetExhausted));
+            if let Some(reason) = reason {
+                attempts.push(Attempt { error, delay: None });
+                return Err(RetryError { reason, attempts });
+            }
+
+            sleep(delay);
+            waited += delay;
+            attempts.push(Attempt {
+                error,
+                delay: Some(delay),
+            });
+        }
+    }
+
+    fn delay(&self, retry: u32, rng: &mut u64) -> Duration {
+        let backoff = self
+            .initial_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        // xorshift64, good enough to spread retries out.
+        *rng ^= *rng << 13;
+        *rng ^= *rng >> 7;
+        *rng ^= *rng << 17;
+        let half = backoff / 2;
+        // Backoffs are capped by `max_delay`, far below the 584 years u64 nanos can hold.
+        let half_nanos = u64::try_from(half.as_nanos()).unwrap_or(u64::MAX);
+        half + Duration::from_nanos(*rng % half_nanos.saturating_add(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::BackendError;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 4,
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+            budget: Duration::from_secs(1),
+            seed: 42,
+        }
+    }
+
+    // Runs `op` with a sleep that only records the delays.
+    fn run<T>(
+        policy: &RetryPolicy,
+        op: impl FnMut() -> Result<T, ModernTxError>,
+    ) -> (Result<T, RetryError>, Vec<Duration>) {
+        let mut slept = Vec::new();
+        let result = policy.run_with_sleep(|delay| slept.push(delay), op);
+        (result, slept)
+    }
+
+    #[test]
+    fn retries_transient_errors_with_growing_jittered_delays() {
+        let mut failures = vec![
+            BackendError::Timeout,
+            BackendError::RateLimited,
+            BackendError::MempoolFull,
+        ];
+        let (result, slept) = run(&policy(), || match failures.pop() {
+            Some(failure) => Err(failure.into()),
+            None => Ok("tx id"),
+        });
+
+        assert_eq!(result, Ok("tx id"));
+        assert_eq!(slept.len(), 3);
+        let bounds = [(50, 100), (100, 200), (150, 300)];
+        for (delay, (min, max)) in slept.iter().zip(bounds) {
+            assert!(
+                (Duration::from_millis(min)..=Duration::from_millis(max)).contains(delay),
+                "{:?} not in {}..={}ms",
+                delay,
+                min,
+                max
+            );
+        }
+    }
+
+    #[test]
+    fn jitter_spans_long_delays() {
+        let policy = RetryPolicy {
+            max_attempts: 50,
+            initial_delay: Duration::from_secs(20),
+            max_delay: Duration::from_secs(20),
+            budget: Duration::from_secs(3600),
+            seed: 7,
+        };
+        let (_, slept) = run(&policy, || -> Result<(), _> {
+            Err(BackendError::Timeout.into())
+        });
+
+        assert_eq!(slept.len(), 49);
+        let range = Duration::from_secs(10)..=Duration::from_secs(20);
+        assert!(slept.iter().all(|delay| range.contains(delay)));
+        // Truncated jitter would keep every delay within 4.3s of the lower bound.
+        assert!(slept.iter().any(|delay| *delay > Duration::from_secs(15)));
+    }
+
+    #[test]
+    fn never_retries_permanent_errors() {
+        let mut calls = 0;
+        let (result, slept) = run(&policy(), || -> Result<(), _> {
+            calls += 1;
+            Err(ModernTxError::InsufficientFunds)
+        });
+
+        let err = result.unwrap_err();
+        assert_eq!(calls, 1);
+        assert!(slept.is_empty());
+        assert_eq!(err.reason, GiveUp::Permanent);
+        assert_eq!(err.last(), &ModernTxError::InsufficientFunds);
+    }
+
+    #[test]
+    fn lists_every_attempt_when_giving_up() {
+        let (result, slept) = run(&policy(), || -> Result<(), _> {
+            Err(BackendError::Timeout.into())
+        });
+
+        let err = result.unwrap_err();
+        assert_eq!(err.reason, GiveUp::AttemptsExhausted);
+        assert_eq!(err.attempts.len(), 4);
+        assert_eq!(slept.len(), 3);
+        assert_eq!(err.attempts[3].delay, None);
+        assert!(err.to_string().starts_with(
+            "Gave up after 4 attempt(s) (attempts exhausted): \
+             1: Chain backend error: request timed out; 2: "
+        ));
+    }
+
+    #[test]
+    fn stops_when_the_budget_is_spent() {
+        let policy = RetryPolicy {
+            max_attempts: 100,
+            budget: Duration::from_millis(500),
+            ..policy()
+        };
+        let (result, slept) = run(&policy, || -> Result<(), _> {
+            Err(BackendError::MempoolFull.into())
+        });
+
+        let err = result.unwrap_err();
+        assert_eq!(err.reason, GiveUp::BudgetExhausted);
+        assert!(slept.iter().sum::<Duration>() <= Duration::from_millis(500));
+        assert_eq!(err.attempts.len(), slept.len() + 1);
+    }
+}
@@ -0,0 +1,357 @@
+use sha2::{Digest, Sha256};
+
+use crate::ModernTxError;
+use crate::address::Address;
+use crate::amount::{Amount, Asset};
+use crate::context::{ContextError, TxContext};
+use crate::oracle::table::StaticOracle;
+use crate::oracle::{PayWith, PriceOracle};
+use crate::wallet::Wallet;
+
+/// One `send <currency> <amount> to address <address> [paying with <currency> [up to <amount>]]
+/// [key <key>]` line of a transaction script. `to <label>` sends to an address book entry
+/// instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SendTx {
+    pub amount: Amount,
+    /// An address or an address book label.
+    pub address: String,
+    /// The asset to convert from, if the send is not paid in its own currency.
+    pub pay_with: Option<PayWith>,
+    pub key: Option<String>,
+}
+
+impl SendTx {
+    /// The explicit idempotency key, or one derived from currency, amount, address,
+    /// the caller's `batch` id and the `index` in it, e.g. the line number, so the same
+    /// send at the same position of the same batch maps to the same key. Different
+    /// batches need different ids, or their sends are taken for replays.
+    pub fn idempotency_key(&self, batch: &str, index: u64) -> String {
+        if let Some(key) = &self.key {
+            return key.clone();
+        }
+        let mut input = format!(
+            "{}:{}:{}:{}:{}",
+            self.amount.asset(),
+            self.amount.base_units(),
+            self.address,
+            batch,
+            index
+        );
+        if let Some(pay_with) = self.pay_with {
+            input.push_str(&format!(":{}", pay_with.asset));
+        }
+        Sha256::digest(input.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// Sends idempotently under the key for `index` in `batch`. A send paid with another
+    /// currency is quoted at the `accepted` prices and sent at the current prices of
+    /// `oracle`, see `Wallet::send_converted`.
+    pub fn execute(
+        &self,
+        wallet: &mut Wallet,
+        batch: &str,
+        index: u64,
+        oracle: &impl PriceOracle,
+        accepted: &impl PriceOracle,
+    ) -> Result<Address, ModernTxError> {
+        let key = self.idempotency_key(batch, index);
+        match self.pay_with {
+            Some(pay_with) => wallet.send_converted_idempotent(
+                &key,
+                oracle,
+                accepted,
+                self.amount,
+                &self.address,
+                pay_with,
+            ),
+            None => wallet.send_idempotent(&key, self.amount, &self.address),
+        }
+    }
+}
+
+/// The outcome of a single non-empty script line, identified by its 1-based line number.
+#[derive(Debug)]
+pub struct LineResult {
+    pub line: usize,
+    pub result: Result<Address, ContextError>,
+}
+
+/// Parses one line. Blank lines and `#` comments yield `Ok(None)`.
+pub fn parse_line(line: &str) -> Result<Option<SendTx>, ModernTxError> {
+    let code = line.split_once('#').map_or(line, |(code, _)| code);
+    let tokens: Vec<&str> = code.split_whitespace().collect();
+    match tokens.as_slice() {
+        [] => Ok(None),
+        ["send", currency, amount, "to", recipient @ ..] => {
+            let (address, rest) = match recipient {
+                ["address", address, rest @ ..] => (address, rest),
+                [label, rest @ ..] if *label != "address" => (label, rest),
+                _ => return Err(ModernTxError::InvalidFormat),
+            };
+            let (pay_with, rest) = match rest {
+                ["paying", "with", currency, rest @ ..] => {
+                    let asset: Asset = currency.parse()?;
+                    let (max_cost, rest) = match rest {
+                        ["up", "to", max_cost, rest @ ..] => {
+                            (Some(Amount::parse_decimal(asset, max_cost)?), rest)
+                        }
+                        rest => (None, rest),
+                    };
+                    (Some(PayWith { asset, max_cost }), rest)
+                }
+                rest => (None, rest),
+            };
+            let key = match rest {
+                [] => None,
+                ["key", key] => Some(key.to_string()),
+                _ => return Err(ModernTxError::InvalidFormat),
+            };
+            let asset: Asset = currency.parse()?;
+            Ok(Some(SendTx {
+                amount: Amount::parse_decimal(asset, amount)?,
+                address: address.to_string(),
+                pay_with,
+                key,
+            }))
+        }
+        _ => Err(ModernTxError::InvalidFormat),
+    }
+}
+
+/// Parses every line of `contents`, keeping line numbers so malformed lines can be reported.
+pub fn parse_script(contents: &str) -> Vec<(usize, Result<SendTx, ModernTxError>)> {
+    contents
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| parse_line(line).transpose().map(|tx| (i + 1, tx)))
+        .collect()
+}
+
+/// The prices of `oracle` for every send paid with another currency, taken when a run
+/// starts, so conversions can be checked against the price the run was started with.
+pub fn accept_prices<'a>(
+    sends: impl IntoIterator<Item = &'a SendTx>,
+    oracle: &impl PriceOracle,
+) -> StaticOracle {
+    let mut accepted = StaticOracle::new();
+    for tx in sends {
+        // A missing price fails the send when it is quoted.
+        if let Some(pay_with) = tx.pay_with
+            && let Ok(price) = oracle.price(tx.amount.asset(), pay_with.asset)
+        {
+            accepted.insert(price);
+        }
+    }
+    accepted
+}
+
+/// Executes every send of the script against `wallet`. A failing line does not stop the run.
+/// Sends are idempotent per line of the script identified by `script`, so running the same
+/// script twice does not debit twice. Sends `paying with` another currency are converted
+/// through `oracle`, within the slippage limit of the prices the run started with.
+pub fn run_script(
+    wallet: &mut Wallet,
+    script: &str,
+    contents: &str,
+    oracle: &impl PriceOracle,
+) -> Vec<LineResult> {
+    let parsed = parse_script(contents);
+    let accepted = accept_prices(parsed.iter().filter_map(|(_, tx)| tx.as_ref().ok()), oracle);
+    parsed
+        .into_iter()
+        .map(|(line, tx)| {
+            let result = match tx {
+                Ok(tx) => tx
+                    .execute(wallet, script, line as u64, oracle, &accepted)
+                    .with_context(|| format!("while sending {} to {}", tx.amount, tx.address)),
+                Err(err) => Err(err).context("while parsing a send"),
+            };
+            LineResult { line, result }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history;
+    use crate::oracle::Price;
+
+    #[test]
+    fn parses_send_lines_and_skips_comments() {
+        let script =
+            "# payouts\n\nsend btc 0.5 to address 1BoatSLRHtKNngkdXEeobR76b53LETtpyT # ok\n";
+        let parsed = parse_script(script);
+        assert_eq!(
+            parsed,
+            vec![(
+                3,
+                Ok(SendTx {
+                    amount: "0.5 btc".parse().unwrap(),
+                    address: "1BoatSLRHtKNngkdXEeobR76b53LETtpyT".to_string(),
+                    pay_with: None,
+                    key: None,
+                })
+            )]
+        );
+    }
+
+    #[test]
+    fn parses_labels_and_conversions() {
+        assert_eq!(
+            parse_line("send eth 2 to alice key payout-1"),
+            Ok(Some(SendTx {
+                amount: "2 eth".parse().unwrap(),
+                address: "alice".to_string(),
+                pay_with: None,
+                key: Some("payout-1".to_string()),
+            }))
+        );
+        assert_eq!(
+            parse_line("send eth 2 to alice paying with sol up to 310 key payout-2"),
+            Ok(Some(SendTx {
+                amount: "2 eth".parse().unwrap(),
+                address: "alice".to_string(),
+                pay_with: Some(PayWith {
+                    asset: Asset::Sol,
+                    max_cost: Some("310 sol".parse().unwrap()),
+                }),
+                key: Some("payout-2".to_string()),
+            }))
+        );
+        assert_eq!(
+            parse_line("send eth 2 to alice paying sol"),
+            Err(ModernTxError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn runs_sends_paid_with_another_currency() {
+        let address = "0x52908400098527886E0F7030069857D2E4169EE7";
+        assert_eq!(
+            parse_line(&format!(
+                "send eth 2 to address {} paying with sol",
+                address
+            )),
+            Ok(Some(SendTx {
+                amount: "2 eth".parse().unwrap(),
+                address: address.to_string(),
+                pay_with: Some(PayWith {
+                    asset: Asset::Sol,
+                    max_cost: None,
+                }),
+                key: None,
+            }))
+        );
+
+        let mut oracle = StaticOracle::new();
+        oracle.insert(Price {
+            base: Asset::Eth,
+            rate: "150 sol".parse().unwrap(),
+            timestamp: history::now(),
+        });
+        let mut wallet = Wallet::new();
+        wallet.deposit("400 sol".parse().unwrap()).unwrap();
+        let script = format!(
+            "send eth 2 to address {a} paying with sol\n\
+             send eth 0.5 to address {a} paying with sol up to 75\n",
+            a = address
+        );
+
+        let results = run_script(&mut wallet, "invoices.txt", &script, &oracle);
+        assert!(results[0].result.is_ok());
+        assert!(matches!(
+            results[1].result.as_ref().unwrap_err().tx_error(),
+            Some(ModernTxError::CostLimitExceeded { .. })
+        ));
+        assert_eq!(wallet.balance(Asset::Sol).to_string(), "99.937 sol");
+    }
+
+    #[test]
+    fn malformed_lines_are_invalid_format_with_line_numbers() {
+        let script = "send sol 3 to address\nsend\nreceive btc 1 from address x\n\
+                      send sol 3 to address 11111111111111111111111111111111 key\n";
+        let lines: Vec<usize> = parse_script(script)
+            .into_iter()
+            .map(|(line, tx)| {
+                assert_eq!(tx, Err(ModernTxError::InvalidFormat));
+                line
+            })
+            .collect();
+        assert_eq!(lines, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn runs_test_data_against_wallet() {
+        let contents = std::fs::read_to_string("test_data.txt").unwrap();
+        let mut wallet = Wallet::new();
+        // A little extra on top of the sends to cover their fees.
+        for amount in ["15.001 btc", "10.01 eth", "5.001 sol"] {
+            wallet.deposit(amount.parse().unwrap()).unwrap();
+        }
+
+        let ok_lines: Vec<usize> = run_script(
+            &mut wallet,
+            "test_data.txt",
+            &contents,
+            &StaticOracle::new(),
+        )
+        .into_iter()
+        .filter(|r| r.result.is_ok())
+        .map(|r| r.line)
+        .collect();
+        assert_eq!(ok_lines, vec![1, 2, 7, 8, 9, 13, 14]);
+
+        let balances: Vec<String> = wallet.balances().map(|b| b.to_string()).collect();
+        assert_eq!(
+            balances,
+            vec!["0.0009714 btc", "0.00874 eth", "2.00099 sol"]
+        );
+    }
+
+    #[test]
+    fn failed_sends_carry_amount_and_address_context() {
+        let mut wallet = Wallet::new();
+        let results = run_script(
+            &mut wallet,
+            "failing.txt",
+            "send sol 4 to address 11111111111111111111111111111111\n",
+            &StaticOracle::new(),
+        );
+        let err = results[0].result.as_ref().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "while sending 4 sol to 11111111111111111111111111111111"
+        );
+        assert_eq!(err.tx_error(), Some(&ModernTxError::InsufficientFunds));
+    }
+
+    #[test]
+    fn running_a_script_twice_does_not_debit_twice() {
+        let script = "send sol 1 to address 11111111111111111111111111111111\n\
+                      send sol 1 to address 11111111111111111111111111111111\n\
+                      send sol 0.5 to address 11111111111111111111111111111111 key payout-7\n\
+                      send sol 0.25 to address 11111111111111111111111111111111 key payout-7\n";
+        let mut wallet = Wallet::new();
+        wallet.deposit("5 sol".parse().unwrap()).unwrap();
+
+        let first = run_script(&mut wallet, "payouts.txt", script, &StaticOracle::new());
+        let balance = wallet.balance(Asset::Sol);
+        assert_eq!(balance.to_string(), "2.499985 sol");
+        let err = first[3].result.as_ref().unwrap_err();
+        assert_eq!(
+            err.tx_error(),
+            Some(&ModernTxError::DuplicateTransaction {
+                key: "payout-7".to_string()
+            })
+        );
+
+        let second = run_script(&mut wallet, "payouts.txt", script, &StaticOracle::new());
+        assert!(second[..3].iter().all(|r| r.result.is_ok()));
+        assert_eq!(wallet.balance(Asset::Sol), balance);
+    }
+}
@@ -0,0 +1,704 @@
+use std::cmp::Ordering;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::ModernTxError;
+use crate::address::Address;
+use crate::amount::{Amount, Asset};
+use crate::wallet::{CompletedSend, Wallet};
+
+const SNAPSHOT_FILE: &str = "snapshot";
+const JOURNAL_FILE: &str = "journal";
+
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("i/o error on {}: {source}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+    #[error("corrupt record on line {line} of {}: {reason}", path.display())]
+    CorruptRecord {
+        path: PathBuf,
+        line: usize,
+        reason: String,
+    },
+    #[error("a failed write could not be rolled back from {}, reopen the store", path.display())]
+    Poisoned { path: PathBuf },
+}
+
+// io::Error has no PartialEq, so two i/o errors are equal if their kinds are.
+impl PartialEq for StorageError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                StorageError::Io { path, source },
+                StorageError::Io {
+                    path: other_path,
+                    source: other_source,
+                },
+            ) => path == other_path && source.kind() == other_source.kind(),
+            (
+                StorageError::CorruptRecord { path, line, reason },
+                StorageError::CorruptRecord {
+                    path: other_path,
+                    line: other_line,
+                    reason: other_reason,
+                },
+            ) => path == other_path && line == other_line && reason == other_reason,
+            (StorageError::Poisoned { path }, StorageError::Poisoned { path: other_path }) => {
+                path == other_path
+            }
+            _ => false,
+        }
+    }
+}
+
+/// What `WalletStore::open` found on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Recovery {
+    /// Journal records applied on top of the snapshot.
+    pub replayed: usize,
+    /// Whether the last journal record was cut off by a crash and discarded.
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Change {
+    Deposit(Amount),
+    Withdraw(Amount),
+    /// An idempotent send, remembered so its key is not sent again after a restart.
+    Completed(CompletedSend),
+}
+
+/// A completed send as stored in the journal and the snapshot.
+#[derive(Serialize, Deserialize)]
+struct StoredSend {
+    key: String,
+    asset: Asset,
+    base_units: u128,
+    address: String,
+    pay_with: Option<Asset>,
+    result: String,
+}
+
+impl StoredSend {
+    fn encode(done: &CompletedSend) -> String {
+        let stored = StoredSend {
+            key: done.key.clone(),
+            asset: done.amount.asset(),
+            base_units: done.amount.base_units(),
+            address: done.address.clone(),
+            pay_with: done.pay_with,
+            result: done.result.to_string(),
+        };
+        serde_json::to_string(&stored).expect("a stored send always serializes")
+    }
+
+    fn decode(json: &str) -> Result<CompletedSend, String> {
+        let stored: StoredSend = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        Ok(CompletedSend {
+            result: Address::parse(stored.asset, &stored.result).map_err(|e| e.to_string())?,
+            key: stored.key,
+            amount: Amount::from_base_units(stored.asset, stored.base_units),
+            address: stored.address,
+            pay_with: stored.pay_with,
+        })
+    }
+}
+
+/// One applied change. Every record carries a sequence number so records already
+/// contained in the snapshot are never applied twice.
+#[derive(Debug, Clone, PartialEq)]
+struct Record {
+    seq: u64,
+    change: Change,
+}
+
+impl Record {
+    fn payload(&self) -> String {
+        let (op, amount) = match &self.change {
+            Change::Deposit(amount) => ("deposit", amount),
+            Change::Withdraw(amount) => ("withdraw", amount),
+            Change::Completed(done) => {
+                return format!("{} completed {}", self.seq, StoredSend::encode(done));
+            }
+        };
+        format!(
+            "{} {} {} {}",
+            self.seq,
+            op,
+            amount.asset(),
+            amount.base_units()
+        )
+    }
+
+    fn encode(&self) -> String {
+        let payload = self.payload();
+        format!("{} {}\n", payload, checksum(&payload))
+    }
+
+    fn decode(line: &str) -> Result<Record, String> {
+        let (payload, sum) = line.rsplit_once(' ').ok_or("missing checksum")?;
+        if sum != checksum(payload) {
+            return Err("checksum mismatch".to_string());
+        }
+        let mut fields = payload.splitn(3, ' ');
+        let (Some(seq), Some(op), Some(rest)) = (fields.next(), fields.next(), fields.next())
+        else {
+            return Err("wrong number of fields".to_string());
+        };
+        let seq = seq.parse().map_err(|_| "invalid sequence number")?;
+        if op == "completed" {
+            return Ok(Record {
+                seq,
+                change: Change::Completed(StoredSend::decode(rest)?),
+            });
+        }
+        let amount = match rest.split(' ').collect::<Vec<_>>().as_slice() {
+            [asset, base_units] => {
+                let asset: Asset = asset.parse().map_err(|e: ModernTxError| e.to_string())?;
+                Amount::from_base_units(asset, base_units.parse().map_err(|_| "invalid amount")?)
+            }
+            _ => return Err("wrong number of fields".to_string()),
+        };
+        let change = match op {
+            "deposit" => Change::Deposit(amount),
+            "withdraw" => Change::Withdraw(amount),
+            other => return Err(format!("unknown operation {}", other)),
+        };
+        Ok(Record { seq, change })
+    }
+}
+
+fn checksum(payload: &str) -> String {
+    Sha256::digest(payload.as_bytes())[..4]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// A wallet whose balances survive restarts. Every change is appended to a journal
+/// and flushed to disk before it is applied in memory; `compact` folds the journal
+/// into a snapshot. Balances and the keys of completed idempotent sends are persisted,
+/// not the policy's daily totals.
+#[derive(Debug)]
+pub struct WalletStore {
+    dir: PathBuf,
+    wallet: Wallet,
+    journal: File,
+    // Length of the journal up to the last complete record.
+    journal_len: u64,
+    seq: u64,
+    recovery: Recovery,
+    // Set when a failed append left bytes in the journal that could not be removed.
+    poisoned: bool,
+}
+
+impl WalletStore {
+    /// Opens the store in `dir`, creating it if needed, and restores the balances into
+    /// `wallet`, which should be empty. Its fee model and policy are kept.
+    pub fn open(dir: impl AsRef<Path>, mut wallet: Wallet) -> Result<Self, StorageError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).map_err(|source| io_error(&dir, source))?;
+
+        let snapshot_seq = load_snapshot(&dir.join(SNAPSHOT_FILE), &mut wallet)?;
+        let journal_path = dir.join(JOURNAL_FILE);
+        let (seq, recovery) = replay_journal(&journal_path, snapshot_seq, &mut wallet)?;
+        let journal = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&journal_path)
+            .map_err(|source| io_error(&journal_path, source))?;
+        let journal_len = journal
+            .metadata()
+            .map_err(|source| io_error(&journal_path, source))?
+            .len();
+
+        Ok(WalletStore {
+            dir,
+            wallet,
+            journal,
+            journal_len,
+            seq,
+            recovery,
+            poisoned: false,
+        })
+    }
+
+    pub fn wallet(&self) -> &Wallet {
+        &self.wallet
+    }
+
+    pub fn recovery(&self) -> Recovery {
+        self.recovery
+    }
+
+    pub fn deposit(&mut self, amount: Amount) -> Result<(), ModernTxError> {
+        self.update(|wallet| wallet.deposit(amount))
+    }
+
+    /// Sends like `Wallet::send` and journals the debit, fee included.
+    pub fn send(&mut self, amount: Amount, address: &str) -> Result<Address, ModernTxError> {
+        self.update(|wallet| wallet.send(amount, address))
+    }
+
+    /// Runs any wallet operation, e.g. `Wallet::execute` or `Wallet::broadcast_send`,
+    /// on a copy and journals every balance it changed and every idempotent send it
+    /// completed before keeping the copy. This is the only way to change the wallet, so
+    /// no debit can bypass the journal.
+    pub fn update<T>(
+        &mut self,
+        op: impl FnOnce(&mut Wallet) -> Result<T, ModernTxError>,
+    ) -> Result<T, ModernTxError> {
+        let mut next = self.wallet.clone();
+        let result = op(&mut next);
+        let mut changes: Vec<Change> = self
+            .wallet
+            .balances()
+            .zip(next.balances())
+            .filter_map(|(before, after)| {
+                let asset = before.asset();
+                let (before, after) = (before.base_units(), after.base_units());
+                match after.cmp(&before) {
+                    Ordering::Greater => Some(Change::Deposit(Amount::from_base_units(
+                        asset,
+                        after - before,
+                    ))),
+                    Ordering::Less => Some(Change::Withdraw(Amount::from_base_units(
+                        asset,
+                        before - after,
+                    ))),
+                    Ordering::Equal => None,
+                }
+            })
+            .collect();
+        changes.extend(
+            next.completed_sends()
+                .filter(|done| !self.wallet.completed_sends().any(|old| old.key == done.key))
+                .cloned()
+                .map(Change::Completed),
+        );
+        self.append(changes)?;
+        self.wallet = next;
+        result
+    }
+
+    /// Writes all balances to a new snapshot and empties the journal. The snapshot is
+    /// replaced atomically, and records it already contains are skipped on replay, so
+    /// a crash at any point leaves a consistent store.
+    pub fn compact(&mut self) -> Result<(), StorageError> {
+        self.check_poisoned()?;
+        let path = self.dir.join(SNAPSHOT_FILE);
+        let tmp_path = self.dir.join(format!("{}.tmp", SNAPSHOT_FILE));
+        let mut contents = format!("seq {}\n", self.seq);
+        for balance in self.wallet.balances() {
+            contents.push_str(&format!("{} {}\n", balance.asset(), balance.base_units()));
+        }
+        for done in self.wallet.completed_sends() {
+            contents.push_str(&format!("completed {}\n", StoredSend::encode(done)));
+        }
+
+        let mut tmp = File::create(&tmp_path).map_err(|source| io_error(&tmp_path, source))?;
+        tmp.write_all(contents.as_bytes())
+            .and_then(|()| tmp.sync_all())
+            .map_err(|source| io_error(&tmp_path, source))?;
+        fs::rename(&tmp_path, &path).map_err(|source| io_error(&path, source))?;
+        // The rename is only durable once the directory entry is.
+        sync_dir(&self.dir).map_err(|source| io_error(&self.dir, source))?;
+
+        let journal_path = self.dir.join(JOURNAL_FILE);
+        self.journal
+            .set_len(0)
+            .and_then(|()| self.journal.sync_all())
+            .map_err(|source| io_error(&journal_path, source))?;
+        self.journal_len = 0;
+        Ok(())
+    }
+
+    /// Appends one record per change in a single write and flushes them. If that fails,
+    /// the journal is cut back to its last complete record, so a partial line is never
+    /// followed by another record or replayed as a change the caller was told had
+    /// failed. If even that fails, the store refuses further changes until it is
+    /// reopened, which discards the partial line.
+    fn append(&mut self, changes: Vec<Change>) -> Result<(), StorageError> {
+        self.check_poisoned()?;
+        if changes.is_empty() {
+            return Ok(());
+        }
+        let mut seq = self.seq;
+        let mut lines = String::new();
+        for change in changes {
+            seq += 1;
+            lines.push_str(&Record { seq, change }.encode());
+        }
+        let path = self.dir.join(JOURNAL_FILE);
+        let written = self
+            .journal
+            .write_all(lines.as_bytes())
+            .and_then(|()| self.journal.sync_data());
+        if let Err(source) = written {
+            if self
+                .journal
+                .set_len(self.journal_len)
+                .and_then(|()| self.journal.sync_data())
+                .is_err()
+            {
+                self.poisoned = true;
+            }
+            return Err(io_error(&path, source));
+        }
+        self.journal_len += lines.len() as u64;
+        self.seq = seq;
+        Ok(())
+    }
+
+    fn check_poisoned(&self) -> Result<(), StorageError> {
+        if self.poisoned {
+            return Err(StorageError::Poisoned {
+                path: self.dir.join(JOURNAL_FILE),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+// Directories cannot be opened as files elsewhere, renames are durable on their own.
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+fn io_error(path: &Path, source: io::Error) -> StorageError {
+    StorageError::Io {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+fn corrupt(path: &Path, line: usize, reason: impl Into<String>) -> StorageError {
+    StorageError::CorruptRecord {
+        path: path.to_path_buf(),
+        line,
+        reason: reason.into(),
+    }
+}
+
+/// Deposits the snapshot balances into `wallet`, remembers its completed sends and returns the snapshot's sequence
+/// number, 0 if there is no snapshot yet.
+fn load_snapshot(path: &Path, wallet: &mut Wallet) -> Result<u64, StorageError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(source) => return Err(io_error(path, source)),
+    };
+
+    let mut seq = None;
+    for (i, line) in contents.lines().enumerate() {
+        match (line.split_once(' '), seq) {
+            (Some(("seq", n)), None) => {
+                seq = Some(
+                    n.parse()
+                        .map_err(|_| corrupt(path, i + 1, "invalid sequence number"))?,
+                )
+            }
+            (Some(("completed", json)), Some(_)) => wallet
+                .remember(StoredSend::decode(json).map_err(|reason| corrupt(path, i + 1, reason))?),
+            (Some((asset, base_units)), Some(_)) => {
+                let asset: Asset = asset
+                    .parse()
+                    .map_err(|e: ModernTxError| corrupt(path, i + 1, e.to_string()))?;
+                let base_units = base_units
+                    .parse()
+                    .map_err(|_| corrupt(path, i + 1, "invalid amount"))?;
+                wallet
+                    .deposit(Amount::from_base_units(asset, base_units))
+                    .map_err(|e| corrupt(path, i + 1, e.to_string()))?;
+            }
+            _ => return Err(corrupt(path, i + 1, "unexpected line")),
+        }
+    }
+    seq.ok_or_else(|| corrupt(path, 1, "missing sequence number"))
+}
+
+/// Applies every journal record newer than the snapshot to `wallet`. A last record
+/// without its newline was cut off mid-write; it is removed from the file, since the
+/// change it describes was never applied or acknowledged.
+fn replay_journal(
+    path: &Path,
+    snapshot_seq: u64,
+    wallet: &mut Wallet,
+) -> Result<(u64, Recovery), StorageError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(source) => return Err(io_error(path, source)),
+    };
+
+    let complete = contents.rfind('\n').map_or(0, |i| i + 1);
+    let truncated = complete < contents.len();
+    if truncated {
+        let file = OpenOptions::new()
+            .write(true)
+            .open(path)
+            .map_err(|source| io_error(path, source))?;
+        file.set_len(complete as u64)
+            .and_then(|()| file.sync_all())
+            .map_err(|source| io_error(path, source))?;
+    }
+
+    let mut seq = snapshot_seq;
+    let mut replayed = 0;
+    for (i, line) in contents[..complete].lines().enumerate() {
+        let record = Record::decode(line).map_err(|reason| corrupt(path, i + 1, reason))?;
+        if record.seq <= snapshot_seq {
+            continue;
+        }
+        if record.seq != seq + 1 {
+            return Err(corrupt(
+                path,
+                i + 1,
+                format!("expected sequence number {}, found {}", seq + 1, record.seq),
+            ));
+        }
+        match record.change {
+            Change::Deposit(amount) => wallet.deposit(amount),
+            Change::Withdraw(amount) => wallet.withdraw(amount),
+            Change::Completed(done) => {
+                wallet.remember(done);
+                Ok(())
+            }
+        }
+        .map_err(|e| corrupt(path, i + 1, e.to_string()))?;
+        seq = record.seq;
+        replayed += 1;
+    }
+    Ok((
+        seq,
+        Recovery {
+            replayed,
+            truncated,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::mock::MockBackend;
+
+    const BTC: &str = "1BoatSLRHtKNngkdXEeobR76b53LETtpyT";
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("wallet-store-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn amount(s: &str) -> Amount {
+        s.parse().unwrap()
+    }
+
+    fn btc_balance(store: &WalletStore) -> String {
+        store.wallet().balance(Asset::Btc).to_string()
+    }
+
+    #[test]
+    fn restores_balances_from_the_journal() {
+        let dir = temp_dir("journal");
+        let mut store = WalletStore::open(&dir, Wallet::new()).unwrap();
+        store.deposit(amount("1 btc")).unwrap();
+        store.send(amount("0.25 btc"), BTC).unwrap();
+        assert!(store.send(amount("5 btc"), BTC).is_err());
+        drop(store);
+
+        let store = WalletStore::open(&dir, Wallet::new()).unwrap();
+        assert_eq!(btc_balance(&store), "0.7499856 btc");
+        assert_eq!(
+            store.recovery(),
+            Recovery {
+                replayed: 2,
+                truncated: false
+            }
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn compaction_does_not_double_apply() {
+        let dir = temp_dir("compact");
+        let mut store = WalletStore::open(&dir, Wallet::new()).unwrap();
+        store.deposit(amount("1 btc")).unwrap();
+        store.compact().unwrap();
+        store.deposit(amount("2 btc")).unwrap();
+        drop(store);
+
+        let mut store = WalletStore::open(&dir, Wallet::new()).unwrap();
+        assert_eq!(btc_balance(&store), "3 btc");
+        assert_eq!(store.recovery().replayed, 1);
+
+        // A crash after writing the snapshot but before emptying the journal.
+        let journal = fs::read(dir.join(JOURNAL_FILE)).unwrap();
+        store.compact().unwrap();
+        fs::write(dir.join(JOURNAL_FILE), journal).unwrap();
+        drop(store);
+
+        let store = WalletStore::open(&dir, Wallet::new()).unwrap();
+        assert_eq!(btc_balance(&store), "3 btc");
+        assert_eq!(store.recovery().replayed, 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn discards_a_truncated_last_record() {
+        let dir = temp_dir("truncated");
+        let mut store = WalletStore::open(&dir, Wallet::new()).unwrap();
+        store.deposit(amount("1 btc")).unwrap();
+        store.deposit(amount("2 btc")).unwrap();
+        drop(store);
+
+        let path = dir.join(JOURNAL_FILE);
+        let journal = fs::read(&path).unwrap();
+        fs::write(&path, &journal[..journal.len() - 5]).unwrap();
+
+        let mut store = WalletStore::open(&dir, Wallet::new()).unwrap();
+        assert_eq!(btc_balance(&store), "1 btc");
+        assert_eq!(
+            store.recovery(),
+            Recovery {
+                replayed: 1,
+                truncated: true
+            }
+        );
+
+        // The partial record is gone, so new records are appended after a clean line.
+        store.deposit(amount("0.5 btc")).unwrap();
+        drop(store);
+        let store = WalletStore::open(&dir, Wallet::new()).unwrap();
+        assert_eq!(btc_balance(&store), "1.5 btc");
+        assert!(!store.recovery().truncated);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_a_corrupt_record() {
+        let dir = temp_dir("corrupt");
+        let mut store = WalletStore::open(&dir, Wallet::new()).unwrap();
+        store.deposit(amount("1 btc")).unwrap();
+        store.deposit(amount("2 btc")).unwrap();
+        drop(store);
+
+        let path = dir.join(JOURNAL_FILE);
+        let journal = fs::read_to_string(&path).unwrap();
+        fs::write(&path, journal.replacen("100000000", "900000000", 1)).unwrap();
+
+        let err = WalletStore::open(&dir, Wallet::new()).unwrap_err();
+        assert_eq!(
+            err,
+            StorageError::CorruptRecord {
+                path,
+                line: 1,
+                reason: "checksum mismatch".to_string(),
+            }
+        );
+        assert!(matches!(
+            ModernTxError::from(err),
+            ModernTxError::StorageError(_)
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_failed_append_changes_nothing() {
+        let dir = temp_dir("failed-append");
+        let mut store = WalletStore::open(&dir, Wallet::new()).unwrap();
+        store.deposit(amount("1 btc")).unwrap();
+
+        // A read-only handle fails the write and the rollback, poisoning the store.
+        let path = dir.join(JOURNAL_FILE);
+        store.journal = File::open(&path).unwrap();
+        assert!(matches!(
+            store.deposit(amount("2 btc")),
+            Err(ModernTxError::StorageError(StorageError::Io { .. }))
+        ));
+        assert_eq!(btc_balance(&store), "1 btc");
+        assert_eq!(
+            store.deposit(amount("2 btc")),
+            Err(ModernTxError::StorageError(StorageError::Poisoned {
+                path: path.clone()
+            }))
+        );
+        drop(store);
+
+        let mut store = WalletStore::open(&dir, Wallet::new()).unwrap();
+        assert_eq!(btc_balance(&store), "1 btc");
+        store.deposit(amount("2 btc")).unwrap();
+        assert_eq!(store.seq, 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn journals_every_debiting_path() {
+        let dir = temp_dir("update");
+        let mut store = WalletStore::open(&dir, Wallet::new()).unwrap();
+        store.deposit(amount("1 btc")).unwrap();
+        store
+            .update(|wallet| wallet.send_idempotent("payout-1", amount("0.25 btc"), BTC))
+            .unwrap();
+        let mut backend = MockBackend::new().with_balance(amount("1 btc"));
+        store
+            .update(|wallet| wallet.broadcast_send(&mut backend, amount("0.25 btc"), BTC, 0))
+            .unwrap();
+        // Failed operations change no balance and write no record.
+        assert!(
+            store
+                .update(|wallet| wallet.send(amount("5 btc"), BTC))
+                .is_err()
+        );
+        let balance = btc_balance(&store);
+        drop(store);
+
+        let store = WalletStore::open(&dir, Wallet::new()).unwrap();
+        assert_eq!(btc_balance(&store), balance);
+        assert_eq!(store.recovery().replayed, 4);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn completed_sends_survive_a_restart() {
+        let dir = temp_dir("completed");
+        let mut store = WalletStore::open(&dir, Wallet::new()).unwrap();
+        store.deposit(amount("1 btc")).unwrap();
+        let send = |store: &mut WalletStore| {
+            store.update(|wallet| wallet.send_idempotent("payout-1", amount("0.25 btc"), BTC))
+        };
+        send(&mut store).unwrap();
+        drop(store);
+
+        let mut store = WalletStore::open(&dir, Wallet::new()).unwrap();
+        let balance = btc_balance(&store);
+        assert_eq!(send(&mut store).unwrap().to_string(), BTC);
+        assert_eq!(btc_balance(&store), balance);
+
+        store.compact().unwrap();
+        drop(store);
+        let mut store = WalletStore::open(&dir, Wallet::new()).unwrap();
+        assert_eq!(send(&mut store).unwrap().to_string(), BTC);
+        assert_eq!(btc_balance(&store), balance);
+        assert_eq!(
+            store.update(|wallet| wallet.send_idempotent("payout-1", amount("0.5 btc"), BTC)),
+            Err(ModernTxError::DuplicateTransaction {
+                key: "payout-1".to_string()
+            })
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
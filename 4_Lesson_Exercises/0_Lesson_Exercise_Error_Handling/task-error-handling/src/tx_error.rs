@@ -0,0 +1,139 @@
+use thiserror::Error;
+
+use crate::{ClassicTxError, ModernTxError};
+
+/// One error type for code that mixes legacy `ClassicTxError` functions with
+/// `ModernTxError` ones: both convert into it with `?`, and it converts back into
+/// either without losing anything.
+#[derive(Debug, Error, PartialEq)]
+#[error(transparent)]
+pub struct TxError(ModernTxError);
+
+impl TxError {
+    pub fn as_modern(&self) -> &ModernTxError {
+        &self.0
+    }
+
+    pub fn into_classic(self) -> ClassicTxError {
+        self.0.into()
+    }
+}
+
+impl From<ModernTxError> for TxError {
+    fn from(err: ModernTxError) -> Self {
+        TxError(err)
+    }
+}
+
+impl From<ClassicTxError> for TxError {
+    fn from(err: ClassicTxError) -> Self {
+        TxError(err.into())
+    }
+}
+
+impl From<ClassicTxError> for ModernTxError {
+    fn from(err: ClassicTxError) -> Self {
+        match err {
+            ClassicTxError::InvalidFormat => ModernTxError::InvalidFormat,
+            ClassicTxError::InsufficientFunds => ModernTxError::InsufficientFunds,
+            ClassicTxError::Unauthorized {
+                rule,
+                limit,
+                attempted,
+            } => ModernTxError::Unauthorized {
+                rule,
+                limit,
+                attempted,
+            },
+            ClassicTxError::SpecificTxError(err) => ModernTxError::SpecificTxError(err),
+            ClassicTxError::InvalidAddress => ModernTxError::InvalidAddress,
+            ClassicTxError::Other(err) => err,
+        }
+    }
+}
+
+impl From<ModernTxError> for ClassicTxError {
+    fn from(err: ModernTxError) -> Self {
+        match err {
+            ModernTxError::InvalidFormat => ClassicTxError::InvalidFormat,
+            ModernTxError::InsufficientFunds => ClassicTxError::InsufficientFunds,
+            ModernTxError::Unauthorized {
+                rule,
+                limit,
+                attempted,
+            } => ClassicTxError::Unauthorized {
+                rule,
+                limit,
+                attempted,
+            },
+            ModernTxError::SpecificTxError(err) => ClassicTxError::SpecificTxError(err),
+            ModernTxError::InvalidAddress => ClassicTxError::InvalidAddress,
+            other => ClassicTxError::Other(other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error_code::tests::one_of_each;
+
+    #[test]
+    fn every_modern_variant_round_trips_through_classic() {
+        for err in one_of_each() {
+            let display = err.to_string();
+            let classic = ClassicTxError::from(err);
+            assert_eq!(classic.to_string(), display);
+            let back = ModernTxError::from(classic);
+            assert_eq!(back.to_string(), display);
+        }
+        for (err, copy) in one_of_each().into_iter().zip(one_of_each()) {
+            assert_eq!(ModernTxError::from(ClassicTxError::from(err)), copy);
+        }
+    }
+
+    #[test]
+    fn shared_variants_do_not_fall_back_to_other() {
+        for err in one_of_each() {
+            let shared = matches!(
+                err,
+                ModernTxError::InvalidFormat
+                    | ModernTxError::InsufficientFunds
+                    | ModernTxError::Unauthorized { .. }
+                    | ModernTxError::SpecificTxError(_)
+                    | ModernTxError::InvalidAddress
+            );
+            let classic = ClassicTxError::from(err);
+            assert_eq!(!shared, matches!(classic, ClassicTxError::Other(_)));
+
+            let display = classic.to_string();
+            let round_trip = ClassicTxError::from(ModernTxError::from(classic));
+            assert_eq!(round_trip.to_string(), display);
+        }
+    }
+
+    #[test]
+    fn facade_accepts_both_enums() {
+        fn legacy() -> Result<(), ClassicTxError> {
+            Err(ClassicTxError::InvalidAddress)
+        }
+        fn modern() -> Result<(), ModernTxError> {
+            Err(ModernTxError::InsufficientFunds)
+        }
+        fn mixed(first: bool) -> Result<(), TxError> {
+            if first {
+                legacy()?;
+            }
+            modern()?;
+            Ok(())
+        }
+
+        let err = mixed(true).unwrap_err();
+        assert_eq!(err.as_modern(), &ModernTxError::InvalidAddress);
+        assert_eq!(err.into_classic(), ClassicTxError::InvalidAddress);
+        assert_eq!(
+            mixed(false).unwrap_err().to_string(),
+            "Insufficient funds for transaction"
+        );
+    }
+}
@@ -0,0 +1,999 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use ed25519_dalek::{Signature, VerifyingKey};
+
+use crate::ModernTxError;
+use crate::address::{Address, Network};
+use crate::address_book::AddressBook;
+use crate::amount::{Amount, Asset};
+use crate::backend::{ChainBackend, Transfer};
+use crate::fee::FeeModel;
+use crate::history::{self, History, HistoryEntry, Status};
+use crate::multisig::{Multisig, PendingSend};
+use crate::oracle::{ConversionLimits, PayWith, PriceOracle, Quote};
+use crate::policy::{self, Policy};
+
+// How many completed idempotent sends are remembered for replays.
+const REMEMBERED_SENDS: usize = 1024;
+
+/// A send remembered under its idempotency key, see `Wallet::send_idempotent`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CompletedSend {
+    pub(crate) key: String,
+    pub(crate) amount: Amount,
+    pub(crate) address: String,
+    pub(crate) pay_with: Option<Asset>,
+    pub(crate) result: Address,
+}
+
+/// A multi-currency wallet holding exact balances in base units per asset.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Wallet {
+    balances: BTreeMap<Asset, u128>,
+    fees: FeeModel,
+    policy: Policy,
+    completed: VecDeque<CompletedSend>,
+    network: Network,
+    address_book: AddressBook,
+    history: History,
+    multisig: Multisig,
+    conversion: ConversionLimits,
+}
+
+impl Wallet {
+    pub fn new() -> Self {
+        Wallet::default()
+    }
+
+    pub fn with_fee_model(mut self, fees: FeeModel) -> Self {
+        self.fees = fees;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sends to addresses of any other network are rejected. Defaults to mainnet.
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    pub fn with_address_book(mut self, address_book: AddressBook) -> Self {
+        self.address_book = address_book;
+        self
+    }
+
+    /// Sends above the thresholds of `multisig` have to be proposed and approved
+    /// instead of sent directly.
+    pub fn with_multisig(mut self, multisig: Multisig) -> Self {
+        self.multisig = multisig;
+        self
+    }
+
+    pub fn with_conversion_limits(mut self, conversion: ConversionLimits) -> Self {
+        self.conversion = conversion;
+        self
+    }
+
+    pub fn balance(&self, asset: Asset) -> Amount {
+        let base_units = self.balances.get(&asset).copied().unwrap_or(0);
+        Amount::from_base_units(asset, base_units)
+    }
+
+    /// All supported assets in a fixed order, including empty ones.
+    pub fn balances(&self) -> impl Iterator<Item = Amount> + '_ {
+        Asset::ALL.into_iter().map(|asset| self.balance(asset))
+    }
+
+    pub fn deposit(&mut self, amount: Amount) -> Result<(), ModernTxError> {
+        let balance = self.balances.entry(amount.asset()).or_insert(0);
+        *balance = balance
+            .checked_add(amount.base_units())
+            .ok_or(ModernTxError::Overflow)?;
+        Ok(())
+    }
+
+    pub fn withdraw(&mut self, amount: Amount) -> Result<(), ModernTxError> {
+        let balance = self.balances.entry(amount.asset()).or_insert(0);
+        *balance = balance
+            .checked_sub(amount.base_units())
+            .ok_or(ModernTxError::InsufficientFunds)?;
+        Ok(())
+    }
+
+    /// Sends without any approvals, see `send_approved`.
+    pub fn send(&mut self, amount: Amount, address: &str) -> Result<Address, ModernTxError> {
+        self.send_approved(amount, address, 0)
+    }
+
+    /// Sends at most once per `key`. Replaying a completed send with the same amount,
+    /// address and currency paid with returns its original result without debiting
+    /// again, reusing the key for a different send is a `DuplicateTransaction`. Failed
+    /// sends change nothing and are not remembered, so they can be retried.
+    pub fn send_idempotent(
+        &mut self,
+        key: &str,
+        amount: Amount,
+        address: &str,
+    ) -> Result<Address, ModernTxError> {
+        self.idempotent(key, amount, address, None, |wallet| {
+            wallet.send(amount, address)
+        })
+    }
+
+    /// `send_converted` without approvals at most once per `key`, see `send_idempotent`.
+    pub fn send_converted_idempotent(
+        &mut self,
+        key: &str,
+        oracle: &impl PriceOracle,
+        accepted: &impl PriceOracle,
+        amount: Amount,
+        address: &str,
+        pay_with: PayWith,
+    ) -> Result<Address, ModernTxError> {
+        self.idempotent(key, amount, address, Some(pay_with.asset), |wallet| {
+            wallet.send_converted(oracle, accepted, amount, address, pay_with, 0)
+        })
+    }
+
+    /// Looks `to` up in the address book, or validates it as an address for the chain of
+    /// `asset` if there is no such label, and checks it belongs to the wallet's network.
+    /// Only BTC addresses encode a network, others are assumed to match unless the
+    /// address book tags them otherwise.
+    pub fn resolve(&self, asset: Asset, to: &str) -> Result<Address, ModernTxError> {
+        let (address, network) = match self.address_book.get(to) {
+            Some(entry) if entry.asset != asset => {
+                return Err(ModernTxError::LabelCurrencyMismatch {
+                    label: to.to_string(),
+                    expected: asset,
+                    found: entry.asset,
+                });
+            }
+            Some(entry) => (entry.address.clone(), entry.network),
+            None => {
+                let address = Address::parse(asset, to)?;
+                let network = address.network().unwrap_or(self.network);
+                (address, network)
+            }
+        };
+        if network != self.network {
+            return Err(ModernTxError::NetworkMismatch {
+                address: address.to_string(),
+                expected: self.network,
+                found: network,
+            });
+        }
+        Ok(address)
+    }
+
+    /// Resolves `address`, which may also be an address book label, checks the send
+    /// against the policy, then debits the amount plus the estimated fee. Nothing is
+    /// debited if any step fails. Every attempt is recorded in the history.
+    pub fn send_approved(
+        &mut self,
+        amount: Amount,
+        address: &str,
+        approvers: usize,
+    ) -> Result<Address, ModernTxError> {
+        let result = self.try_send(amount, address, approvers);
+        self.record(amount, address, result.as_ref().map(|(_, fee)| *fee));
+        result.map(|(address, _)| address)
+    }
+
+    /// Like `send_approved`, but executes the transfer on a chain through `backend`,
+    /// using its fee estimate and nonce. The wallet is only debited once the backend
+    /// accepted the transfer. Returns the transaction id. A `Timeout` may come after the
+    /// backend accepted the transfer, and calling this again sends it a second time under
+    /// a new nonce, so check the chain before retrying one.
+    pub fn broadcast_send(
+        &mut self,
+        backend: &mut impl ChainBackend,
+        amount: Amount,
+        address: &str,
+        approvers: usize,
+    ) -> Result<String, ModernTxError> {
+        let result = self.try_broadcast(backend, amount, address, approvers);
+        self.record(amount, address, result.as_ref().map(|(_, fee)| *fee));
+        result.map(|(tx_id, _)| tx_id)
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Prices sending `amount` to `address` when paying in `pay_with`, including the
+    /// network fee, with a price that is at most `ConversionLimits::max_price_age` old.
+    pub fn quote(
+        &self,
+        oracle: &impl PriceOracle,
+        amount: Amount,
+        address: &str,
+        pay_with: Asset,
+    ) -> Result<Quote, ModernTxError> {
+        let address = self.resolve(amount.asset(), address)?;
+        let price = oracle.price(amount.asset(), pay_with)?;
+        let age = history::now().saturating_sub(price.timestamp);
+        if age > self.conversion.max_price_age {
+            return Err(ModernTxError::StalePrice {
+                base: amount.asset(),
+                quote: pay_with,
+                age,
+                max_age: self.conversion.max_price_age,
+            });
+        }
+        let fee = self.fees.estimate(&address)?;
+        Ok(Quote {
+            amount,
+            address,
+            fee,
+            price,
+            cost: price.convert(amount)?,
+            cost_fee: price.convert(fee)?,
+        })
+    }
+
+    /// Sends an accepted quote with `approvers`, debiting its cost from the asset paid
+    /// with. The price is fetched again and the send fails if the total cost rose by more
+    /// than `ConversionLimits::max_slippage_bps`, or above `max_cost`. The policy is
+    /// checked against both the amount sent and the cost debited. The history records
+    /// the debited amounts.
+    pub fn send_quoted(
+        &mut self,
+        oracle: &impl PriceOracle,
+        quote: &Quote,
+        max_cost: Option<Amount>,
+        approvers: usize,
+    ) -> Result<Address, ModernTxError> {
+        let address = quote.address.to_string();
+        match self.try_send_quoted(oracle, quote, max_cost, approvers) {
+            Ok((address, current)) => {
+                self.record(current.cost, &address.to_string(), Ok(current.cost_fee));
+                Ok(address)
+            }
+            Err(e) => {
+                self.record(quote.cost, &address, Err(&e));
+                Err(e)
+            }
+        }
+    }
+
+    /// Sends `amount` to `address` paying in another asset, quoted at the `accepted`
+    /// prices, e.g. the ones a script run started with, then sent like `send_quoted`
+    /// at the current prices of `oracle`.
+    pub fn send_converted(
+        &mut self,
+        oracle: &impl PriceOracle,
+        accepted: &impl PriceOracle,
+        amount: Amount,
+        address: &str,
+        pay_with: PayWith,
+        approvers: usize,
+    ) -> Result<Address, ModernTxError> {
+        match self.quote(accepted, amount, address, pay_with.asset) {
+            Ok(quote) => self.send_quoted(oracle, &quote, pay_with.max_cost, approvers),
+            Err(e) => {
+                self.record(amount, address, Err(&e));
+                Err(e)
+            }
+        }
+    }
+
+    /// Appends the attempts `rejected` recorded since it was cloned from this wallet,
+    /// for a scratch copy whose changes are discarded. Sends that went through on the
+    /// copy are recorded as failed with `reason`, since they were rolled back.
+    pub fn keep_history_of(&mut self, rejected: &Wallet, reason: &str) {
+        let seen = self.history.entries().len();
+        for entry in &rejected.history.entries()[seen..] {
+            let mut entry = entry.clone();
+            if entry.status == Status::Sent {
+                entry.status = Status::Failed;
+                entry.fee = None;
+                entry.error = Some(reason.to_string());
+            }
+            self.history.record(entry);
+        }
+    }
+
+    pub fn multisig(&self) -> &Multisig {
+        &self.multisig
+    }
+
+    /// Queues a send for approval without debiting anything and returns its id. The
+    /// address, policy and balance are checked now and again on execution.
+    pub fn propose(&mut self, amount: Amount, address: &str) -> Result<u64, ModernTxError> {
+        let address = self.resolve(amount.asset(), address)?;
+        // Approvals are collected later, so only the other rules can fail yet.
+        self.policy
+            .check(amount, &address, usize::MAX, policy::today())?;
+        self.check_funds(amount, self.fees.estimate(&address)?)?;
+        Ok(self.multisig.propose(amount, address, history::now()))
+    }
+
+    pub fn approve(
+        &mut self,
+        id: u64,
+        key: &VerifyingKey,
+        signature: &Signature,
+    ) -> Result<usize, ModernTxError> {
+        self.multisig.approve(id, key, signature, history::now())
+    }
+
+    pub fn reject(
+        &mut self,
+        id: u64,
+        key: &VerifyingKey,
+        signature: &Signature,
+    ) -> Result<PendingSend, ModernTxError> {
+        self.multisig.reject(id, key, signature, history::now())
+    }
+
+    /// Drops pending sends that waited too long for their approvals.
+    pub fn expire_pending(&mut self) -> Vec<PendingSend> {
+        self.multisig.expire(history::now())
+    }
+
+    /// Debits an approved pending send. Its signatures count as approvers for the
+    /// policy. If the send fails, e.g. because the balance changed, it stays pending.
+    pub fn execute(&mut self, id: u64) -> Result<Address, ModernTxError> {
+        let pending = self.multisig.approved(id, history::now())?.clone();
+        let (address, approvers) = (pending.address.to_string(), pending.approvals());
+        let result = self.settle(pending.amount, pending.address, approvers);
+        self.record(
+            pending.amount,
+            &address,
+            result.as_ref().map(|(_, fee)| *fee),
+        );
+        if result.is_ok() {
+            self.multisig.complete(id);
+        }
+        result.map(|(address, _)| address)
+    }
+
+    // Runs `send` at most once per `key`, see `send_idempotent`.
+    fn idempotent(
+        &mut self,
+        key: &str,
+        amount: Amount,
+        address: &str,
+        pay_with: Option<Asset>,
+        send: impl FnOnce(&mut Self) -> Result<Address, ModernTxError>,
+    ) -> Result<Address, ModernTxError> {
+        if let Some(done) = self.completed.iter().find(|done| done.key == key) {
+            if done.amount == amount && done.address == address && done.pay_with == pay_with {
+                return Ok(done.result.clone());
+            }
+            let duplicate = ModernTxError::DuplicateTransaction {
+                key: key.to_string(),
+            };
+            self.record(amount, address, Err(&duplicate));
+            return Err(duplicate);
+        }
+
+        let result = send(self)?;
+        self.remember(CompletedSend {
+            key: key.to_string(),
+            amount,
+            address: address.to_string(),
+            pay_with,
+            result: result.clone(),
+        });
+        Ok(result)
+    }
+
+    /// The remembered idempotent sends, oldest first, for persisting them.
+    pub(crate) fn completed_sends(&self) -> impl Iterator<Item = &CompletedSend> {
+        self.completed.iter()
+    }
+
+    pub(crate) fn remember(&mut self, done: CompletedSend) {
+        if self.completed.len() == REMEMBERED_SENDS {
+            self.completed.pop_front();
+        }
+        self.completed.push_back(done);
+    }
+
+    // `outcome` is the fee debited on top of the amount, or why the send failed.
+    fn record(&mut self, amount: Amount, address: &str, outcome: Result<Amount, &ModernTxError>) {
+        let (status, fee, error) = match outcome {
+            Ok(fee) => (Status::Sent, Some(fee), None),
+            Err(e) => (Status::Failed, None, Some(e.to_string())),
+        };
+        self.history.record(HistoryEntry {
+            timestamp: history::now(),
+            amount,
+            address: address.to_string(),
+            status,
+            fee,
+            error,
+        });
+    }
+
+    // Returns the resolved address and the fee that was debited on top of the amount.
+    fn try_send(
+        &mut self,
+        amount: Amount,
+        address: &str,
+        approvers: usize,
+    ) -> Result<(Address, Amount), ModernTxError> {
+        let address = self.resolve(amount.asset(), address)?;
+        self.check_threshold(amount)?;
+        self.settle(amount, address, approvers)
+    }
+
+    // Debits a send to a resolved address that needs no further approval.
+    fn settle(
+        &mut self,
+        amount: Amount,
+        address: Address,
+        approvers: usize,
+    ) -> Result<(Address, Amount), ModernTxError> {
+        let day = policy::today();
+        self.policy.check(amount, &address, approvers, day)?;
+        let fee = self.fees.estimate(&address)?;
+        let total = self.check_funds(amount, fee)?;
+        self.withdraw(total)?;
+        self.policy.record(amount, day);
+        Ok((address, fee))
+    }
+
+    // Returns the transaction id and the fee that was debited on top of the amount.
+    fn try_broadcast(
+        &mut self,
+        backend: &mut impl ChainBackend,
+        amount: Amount,
+        address: &str,
+        approvers: usize,
+    ) -> Result<(String, Amount), ModernTxError> {
+        let to = self.resolve(amount.asset(), address)?;
+        self.check_threshold(amount)?;
+        let day = policy::today();
+        self.policy.check(amount, &to, approvers, day)?;
+        let fee = backend.estimate_fee(&to)?;
+        let total = self.check_funds(amount, fee)?;
+        let nonce = backend.get_nonce(amount.asset())?;
+        let tx_id = backend.broadcast(&Transfer {
+            amount,
+            to,
+            fee,
+            nonce,
+        })?;
+        self.withdraw(total)?;
+        self.policy.record(amount, day);
+        Ok((tx_id, fee))
+    }
+
+    // Returns the address and the quote at the current price that was debited.
+    fn try_send_quoted(
+        &mut self,
+        oracle: &impl PriceOracle,
+        quote: &Quote,
+        max_cost: Option<Amount>,
+        approvers: usize,
+    ) -> Result<(Address, Quote), ModernTxError> {
+        let current = self.quote(
+            oracle,
+            quote.amount,
+            &quote.address.to_string(),
+            quote.cost.asset(),
+        )?;
+        let (quoted, actual) = (quote.total()?, current.total()?);
+        if let Some(max_cost) = max_cost
+            && actual.base_units() > max_cost.base_units()
+        {
+            return Err(ModernTxError::CostLimitExceeded { max_cost, actual });
+        }
+        let max_bps = self.conversion.max_slippage_bps;
+        let limit = quoted
+            .base_units()
+            .checked_mul(10_000 + u128::from(max_bps))
+            .ok_or(ModernTxError::Overflow)?
+            / 10_000;
+        if actual.base_units() > limit {
+            return Err(ModernTxError::SlippageExceeded {
+                quoted,
+                actual,
+                max_bps,
+            });
+        }
+
+        self.check_threshold(current.amount)?;
+        let day = policy::today();
+        // Limits apply to the asset sent and to the one paid with.
+        for spent in [current.amount, current.cost] {
+            self.policy.check(spent, &current.address, approvers, day)?;
+        }
+        let total = self.check_funds(current.cost, current.cost_fee)?;
+        self.withdraw(total)?;
+        self.policy.record(current.amount, day);
+        self.policy.record(current.cost, day);
+        Ok((current.address.clone(), current))
+    }
+
+    fn check_threshold(&self, amount: Amount) -> Result<(), ModernTxError> {
+        match self.multisig.exceeded_threshold(amount) {
+            Some(threshold) => Err(ModernTxError::ApprovalRequired { threshold }),
+            None => Ok(()),
+        }
+    }
+
+    // Returns the amount plus fee, if the balance covers both.
+    fn check_funds(&self, amount: Amount, fee: Amount) -> Result<Amount, ModernTxError> {
+        let available = self.balance(amount.asset());
+        if amount.base_units() > available.base_units() {
+            return Err(ModernTxError::InsufficientFunds);
+        }
+        let total = amount
+            .base_units()
+            .checked_add(fee.base_units())
+            .ok_or(ModernTxError::Overflow)?;
+        if total > available.base_units() {
+            return Err(ModernTxError::InsufficientFundsForFee {
+                amount,
+                fee,
+                available,
+            });
+        }
+        Ok(Amount::from_base_units(amount.asset(), total))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{Signer, SigningKey};
+
+    use super::*;
+    use crate::backend::BackendError;
+    use crate::backend::mock::MockBackend;
+    use crate::multisig::Decision;
+    use crate::oracle::Price;
+    use crate::oracle::table::StaticOracle;
+    use crate::policy::PolicyRule;
+
+    fn amount(s: &str) -> Amount {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn deposits_and_withdraws_exact_amounts() -> anyhow::Result<()> {
+        let mut wallet = Wallet::new();
+        wallet.deposit(amount("15 btc"))?;
+        wallet.withdraw(amount("0.5 btc"))?;
+        wallet.withdraw(amount("0.00000001 btc"))?;
+        assert_eq!(wallet.balance(Asset::Btc), amount("14.49999999 btc"));
+        assert_eq!(wallet.balance(Asset::Eth), amount("0 eth"));
+        Ok(())
+    }
+
+    #[test]
+    fn withdraw_more_than_balance_is_insufficient_funds() {
+        let mut wallet = Wallet::new();
+        wallet.deposit(amount("2 sol")).unwrap();
+        assert_eq!(
+            wallet.withdraw(amount("2.000000001 sol")),
+            Err(ModernTxError::InsufficientFunds)
+        );
+        assert_eq!(wallet.balance(Asset::Sol), amount("2 sol"));
+    }
+
+    #[test]
+    fn deposit_past_u128_max_is_overflow() {
+        let mut wallet = Wallet::new();
+        wallet
+            .deposit(Amount::from_base_units(Asset::Eth, u128::MAX))
+            .unwrap();
+        assert_eq!(
+            wallet.deposit(Amount::from_base_units(Asset::Eth, 1)),
+            Err(ModernTxError::Overflow)
+        );
+        assert_eq!(wallet.balance(Asset::Eth).base_units(), u128::MAX);
+    }
+
+    #[test]
+    fn send_checks_address_before_funds() {
+        let mut wallet = Wallet::new();
+        wallet.deposit(amount("1 btc")).unwrap();
+        assert!(matches!(
+            wallet.send(amount("2 btc"), "1BoatSLRHtKNngkdXEeobR76b53LETtpzz"),
+            Err(ModernTxError::InvalidBtcAddress(_))
+        ));
+        assert_eq!(
+            wallet.send(amount("2 btc"), "1BoatSLRHtKNngkdXEeobR76b53LETtpyT"),
+            Err(ModernTxError::InsufficientFunds)
+        );
+        wallet
+            .send(amount("0.25 btc"), "1BoatSLRHtKNngkdXEeobR76b53LETtpyT")
+            .unwrap();
+        assert_eq!(wallet.balance(Asset::Btc), amount("0.7499856 btc"));
+    }
+
+    #[test]
+    fn send_debits_fee_and_reports_when_it_cannot_be_covered() {
+        let mut wallet = Wallet::new();
+        wallet.deposit(amount("1 sol")).unwrap();
+        assert_eq!(
+            wallet.send(amount("1 sol"), "11111111111111111111111111111111"),
+            Err(ModernTxError::InsufficientFundsForFee {
+                amount: amount("1 sol"),
+                fee: amount("0.000005 sol"),
+                available: amount("1 sol"),
+            })
+        );
+        wallet
+            .send(amount("0.999995 sol"), "11111111111111111111111111111111")
+            .unwrap();
+        assert_eq!(wallet.balance(Asset::Sol), amount("0 sol"));
+    }
+
+    #[test]
+    fn fee_model_is_configurable() {
+        let mut wallet = Wallet::new().with_fee_model(FeeModel {
+            eth_gas_price_wei: 0,
+            ..FeeModel::default()
+        });
+        wallet.deposit(amount("2 eth")).unwrap();
+        wallet
+            .send(
+                amount("2 eth"),
+                "0x52908400098527886E0F7030069857D2E4169EE7",
+            )
+            .unwrap();
+        assert_eq!(wallet.balance(Asset::Eth), amount("0 eth"));
+    }
+
+    #[test]
+    fn policy_is_checked_before_funds_and_counts_completed_sends() {
+        let mut wallet = Wallet::new().with_policy(
+            Policy::new()
+                .daily_limit(amount("1 sol"))
+                .required_approvers(1),
+        );
+        let address = "11111111111111111111111111111111";
+        assert!(matches!(
+            wallet.send(amount("0.5 sol"), address),
+            Err(ModernTxError::Unauthorized {
+                rule: PolicyRule::RequiredApprovers,
+                ..
+            })
+        ));
+        assert_eq!(
+            wallet.send_approved(amount("0.5 sol"), address, 1),
+            Err(ModernTxError::InsufficientFunds)
+        );
+
+        wallet.deposit(amount("2 sol")).unwrap();
+        wallet
+            .send_approved(amount("0.75 sol"), address, 1)
+            .unwrap();
+        assert!(matches!(
+            wallet.send_approved(amount("0.5 sol"), address, 1),
+            Err(ModernTxError::Unauthorized {
+                rule: PolicyRule::DailyLimit,
+                ..
+            })
+        ));
+        assert_eq!(wallet.balance(Asset::Sol), amount("1.249995 sol"));
+    }
+
+    #[test]
+    fn sends_to_labels_on_the_wallet_network_only() {
+        let eth = "0x52908400098527886E0F7030069857D2E4169EE7";
+        let mut book = AddressBook::new();
+        book.insert("alice", Asset::Eth, Network::Mainnet, eth)
+            .unwrap();
+        book.insert("bob", Asset::Eth, Network::Testnet, eth)
+            .unwrap();
+        let mut wallet = Wallet::new().with_address_book(book);
+        wallet.deposit(amount("5 eth")).unwrap();
+
+        assert_eq!(
+            wallet.send(amount("2 eth"), "alice").unwrap().to_string(),
+            eth
+        );
+        assert_eq!(
+            wallet.send(amount("1 eth"), "bob"),
+            Err(ModernTxError::NetworkMismatch {
+                address: eth.to_string(),
+                expected: Network::Mainnet,
+                found: Network::Testnet,
+            })
+        );
+        assert_eq!(
+            wallet.send(amount("1 btc"), "alice"),
+            Err(ModernTxError::LabelCurrencyMismatch {
+                label: "alice".to_string(),
+                expected: Asset::Btc,
+                found: Asset::Eth,
+            })
+        );
+        assert!(matches!(
+            wallet.send(
+                amount("1 btc"),
+                "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"
+            ),
+            Err(ModernTxError::NetworkMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn broadcasts_through_the_backend_and_debits_only_on_success() {
+        let address = "0x52908400098527886E0F7030069857D2E4169EE7";
+        let mut backend = MockBackend::new().with_balance(amount("5 eth"));
+        let mut wallet = Wallet::new();
+        wallet.deposit(amount("5 eth")).unwrap();
+
+        backend.fail_next(BackendError::MempoolFull);
+        assert_eq!(
+            wallet.broadcast_send(&mut backend, amount("1 eth"), address, 0),
+            Err(ModernTxError::BackendError(BackendError::MempoolFull))
+        );
+        assert_eq!(wallet.balance(Asset::Eth), amount("5 eth"));
+
+        wallet
+            .broadcast_send(&mut backend, amount("1 eth"), address, 0)
+            .unwrap();
+        wallet
+            .broadcast_send(&mut backend, amount("2 eth"), address, 0)
+            .unwrap();
+        let nonces: Vec<u64> = backend.broadcasts().iter().map(|t| t.nonce).collect();
+        assert_eq!(nonces, vec![0, 1]);
+        assert_eq!(wallet.balance(Asset::Eth), amount("1.99916 eth"));
+        assert_eq!(
+            backend.get_balance(Asset::Eth),
+            Ok(wallet.balance(Asset::Eth))
+        );
+        assert_eq!(wallet.history().by_status(Status::Failed).count(), 1);
+    }
+
+    #[test]
+    fn backend_failures_map_to_typed_errors() {
+        let address = "11111111111111111111111111111111";
+        let mut backend = MockBackend::new().with_balance(amount("1 sol"));
+        let mut wallet = Wallet::new();
+        wallet.deposit(amount("5 sol")).unwrap();
+
+        for failure in [
+            BackendError::Timeout,
+            BackendError::NonceTooLow {
+                expected: 3,
+                got: 2,
+            },
+            BackendError::Rejected("blockhash not found".to_string()),
+        ] {
+            backend.fail_next(failure.clone());
+            let err = wallet
+                .broadcast_send(&mut backend, amount("0.5 sol"), address, 0)
+                .unwrap_err();
+            assert_eq!(err, ModernTxError::BackendError(failure));
+        }
+
+        // The wallet's own ledger covers the send, but the chain does not.
+        assert_eq!(
+            wallet.broadcast_send(&mut backend, amount("2 sol"), address, 0),
+            Err(ModernTxError::BackendError(BackendError::Rejected(
+                "insufficient balance".to_string()
+            )))
+        );
+        assert_eq!(wallet.balance(Asset::Sol), amount("5 sol"));
+    }
+
+    #[test]
+    fn sends_above_the_threshold_wait_for_approvals() {
+        let address = "11111111111111111111111111111111";
+        let signers: Vec<SigningKey> = (1..=3).map(|i| SigningKey::from_bytes(&[i; 32])).collect();
+        let multisig = Multisig::new(
+            "treasury",
+            2,
+            signers.iter().map(|s| s.verifying_key()).collect(),
+        )
+        .unwrap()
+        .threshold(amount("10 sol"));
+        let mut wallet = Wallet::new()
+            .with_multisig(multisig)
+            .with_policy(Policy::new().required_approvers(2));
+        wallet.deposit(amount("30 sol")).unwrap();
+
+        assert_eq!(
+            wallet.send(amount("20 sol"), address),
+            Err(ModernTxError::ApprovalRequired {
+                threshold: amount("10 sol")
+            })
+        );
+        let id = wallet.propose(amount("20 sol"), address).unwrap();
+        let message = wallet
+            .multisig()
+            .pending()
+            .next()
+            .unwrap()
+            .message(Decision::Approve);
+        for signer in &signers[..2] {
+            let signature = signer.sign(&message);
+            wallet
+                .approve(id, &signer.verifying_key(), &signature)
+                .unwrap();
+        }
+        assert_eq!(wallet.balance(Asset::Sol), amount("30 sol"));
+
+        // A failed execution keeps the send pending.
+        wallet.withdraw(amount("15 sol")).unwrap();
+        assert_eq!(wallet.execute(id), Err(ModernTxError::InsufficientFunds));
+        wallet.deposit(amount("15 sol")).unwrap();
+        assert_eq!(wallet.execute(id).unwrap().to_string(), address);
+        assert_eq!(wallet.balance(Asset::Sol), amount("9.999995 sol"));
+        assert_eq!(
+            wallet.execute(id),
+            Err(ModernTxError::UnknownPendingSend { id })
+        );
+    }
+
+    #[test]
+    fn converted_sends_debit_the_asset_paid_with() {
+        let address = "0x52908400098527886E0F7030069857D2E4169EE7";
+        let price = |rate: &str, timestamp| Price {
+            base: Asset::Eth,
+            rate: amount(rate),
+            timestamp,
+        };
+        let mut oracle = StaticOracle::new();
+        oracle.insert(price("150 sol", history::now()));
+        let mut wallet = Wallet::new();
+        wallet.deposit(amount("400 sol")).unwrap();
+
+        let quote = wallet
+            .quote(&oracle, amount("2 eth"), address, Asset::Sol)
+            .unwrap();
+        assert_eq!(quote.total().unwrap(), amount("300.063 sol"));
+        // Within the default limit of 1%.
+        oracle.insert(price("151.5 sol", history::now()));
+        wallet.send_quoted(&oracle, &quote, None, 0).unwrap();
+        assert_eq!(wallet.balance(Asset::Sol), amount("96.93637 sol"));
+        assert_eq!(wallet.balance(Asset::Eth), amount("0 eth"));
+        let sent = wallet.history().entries().last().unwrap();
+        assert_eq!(sent.amount, amount("303 sol"));
+        assert_eq!(sent.fee, Some(amount("0.06363 sol")));
+
+        let accepted = oracle.clone();
+        let quote = wallet
+            .quote(&accepted, amount("0.5 eth"), address, Asset::Sol)
+            .unwrap();
+        oracle.insert(price("160 sol", history::now()));
+        assert!(matches!(
+            wallet.send_quoted(&oracle, &quote, None, 0),
+            Err(ModernTxError::SlippageExceeded { max_bps: 100, .. })
+        ));
+        let pay_with = |asset, max_cost: Option<&str>| PayWith {
+            asset,
+            max_cost: max_cost.map(amount),
+        };
+        assert!(matches!(
+            wallet.send_converted(
+                &oracle,
+                &accepted,
+                amount("0.5 eth"),
+                address,
+                pay_with(Asset::Sol, None),
+                0
+            ),
+            Err(ModernTxError::SlippageExceeded { max_bps: 100, .. })
+        ));
+        assert_eq!(
+            wallet.send_converted(
+                &oracle,
+                &oracle,
+                amount("0.5 eth"),
+                address,
+                pay_with(Asset::Sol, Some("80 sol")),
+                0
+            ),
+            Err(ModernTxError::CostLimitExceeded {
+                max_cost: amount("80 sol"),
+                actual: amount("80.0672 sol"),
+            })
+        );
+        oracle.insert(price("160 sol", history::now() - 301));
+        assert!(matches!(
+            wallet.send_converted(
+                &oracle,
+                &oracle,
+                amount("0.5 eth"),
+                address,
+                pay_with(Asset::Sol, None),
+                0
+            ),
+            Err(ModernTxError::StalePrice { max_age: 300, .. })
+        ));
+        assert_eq!(
+            wallet.send_converted(
+                &oracle,
+                &oracle,
+                amount("0.5 eth"),
+                address,
+                pay_with(Asset::Btc, None),
+                0
+            ),
+            Err(ModernTxError::UnsupportedPair {
+                base: Asset::Eth,
+                quote: Asset::Btc
+            })
+        );
+        assert_eq!(wallet.balance(Asset::Sol), amount("96.93637 sol"));
+    }
+
+    #[test]
+    fn converted_sends_pass_the_policy_of_both_assets() {
+        let address = "0x52908400098527886E0F7030069857D2E4169EE7";
+        let mut oracle = StaticOracle::new();
+        oracle.insert(Price {
+            base: Asset::Eth,
+            rate: amount("150 sol"),
+            timestamp: history::now(),
+        });
+        let policy = Policy::new()
+            .daily_limit(amount("100 sol"))
+            .required_approvers(2);
+        let mut wallet = Wallet::new().with_policy(policy);
+        wallet.deposit(amount("400 sol")).unwrap();
+        let pay_with = PayWith {
+            asset: Asset::Sol,
+            max_cost: None,
+        };
+        let mut send = |approvers| {
+            wallet.send_converted(
+                &oracle,
+                &oracle,
+                amount("0.5 eth"),
+                address,
+                pay_with,
+                approvers,
+            )
+        };
+
+        assert!(matches!(
+            send(0),
+            Err(ModernTxError::Unauthorized {
+                rule: PolicyRule::RequiredApprovers,
+                ..
+            })
+        ));
+        send(2).unwrap();
+        // 75 sol were spent, paying another 75 exceeds the SOL limit.
+        assert!(matches!(
+            send(2),
+            Err(ModernTxError::Unauthorized {
+                rule: PolicyRule::DailyLimit,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn a_key_is_not_replayed_for_a_send_paid_with_another_currency() {
+        let address = "0x52908400098527886E0F7030069857D2E4169EE7";
+        let mut wallet = Wallet::new();
+        wallet.deposit(amount("1 eth")).unwrap();
+        wallet.deposit(amount("400 sol")).unwrap();
+
+        wallet
+            .send_idempotent("payout-1", amount("0.5 eth"), address)
+            .unwrap();
+        assert_eq!(
+            wallet.send_converted_idempotent(
+                "payout-1",
+                &StaticOracle::new(),
+                &StaticOracle::new(),
+                amount("0.5 eth"),
+                address,
+                PayWith {
+                    asset: Asset::Sol,
+                    max_cost: None,
+                }
+            ),
+            Err(ModernTxError::DuplicateTransaction {
+                key: "payout-1".to_string()
+            })
+        );
+        assert_eq!(wallet.balance(Asset::Sol), amount("400 sol"));
+    }
+}